@@ -25,7 +25,6 @@ struct Counter(AtomicU32);
 #[derive(Debug, Default)]
 struct Manager {
     counters: Vec<Counter>,
-    failed: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -33,16 +32,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let manager = ProcessBuilder::<Mutex<Manager>>::new("examples/reusable.bpmn")?
         .task(RUN_COUNTER_PROCESS, run_counter_process()?)
-        .end_event(|input, _name, symbol| {
-            // Act on error end event, update model or inform external system
-            if let Symbol::Error = symbol {
-                input.lock().unwrap().failed = true;
-            }
-            Ok(())
-        })
         .build()?;
 
-    println!("{:?}", manager.run(Default::default())?.into_inner()?);
+    let (result, outcome) = manager.run_with_outcome(Default::default())?;
+    println!("{:?}", result.into_inner()?);
+
+    // Act on error end event, update model or inform external system
+    if outcome.reached_symbol(Symbol::Error) {
+        println!("failed: {:?}", outcome.end_events);
+    }
     Ok(())
 }
 
@@ -1,4 +1,4 @@
-use crate::bpmn::Symbol;
+use crate::bpmn::{Activity, BpmnType, Event, Gateway, Symbol};
 use std::borrow::Cow;
 
 /// Inclusive gateway return type
@@ -77,6 +77,38 @@ impl From<Vec<Cow<'static, str>>> for Inclusive {
     }
 }
 
+/// Gateway decision as a type, usually an enum with one variant per outgoing sequence flow.
+/// Register it with `exclusive_choice` or `inclusive_choice`, and `build` checks that every variant
+/// maps to an outgoing flow of the gateway.
+///
+/// ```
+/// use snurr::FlowChoice;
+///
+/// #[derive(Clone, Copy)]
+/// enum Equal {
+///     Yes,
+///     No,
+/// }
+///
+/// impl FlowChoice for Equal {
+///     const ALL: &'static [Self] = &[Equal::Yes, Equal::No];
+///
+///     fn flow(&self) -> &'static str {
+///         match self {
+///             Equal::Yes => "YES",
+///             Equal::No => "NO",
+///         }
+///     }
+/// }
+/// ```
+pub trait FlowChoice: Sized + 'static {
+    /// Every choice, checked against the outgoing flows when the process is built
+    const ALL: &'static [Self];
+
+    /// Outgoing sequence flow by name or id
+    fn flow(&self) -> &'static str;
+}
+
 /// Exclusive gateway return type
 #[derive(Default, Debug)]
 pub enum Exclusive {
@@ -119,12 +151,15 @@ where
 
 /// Task return type
 #[derive(Default, Debug)]
+#[non_exhaustive]
 pub enum Task {
     /// Use default flow
     #[default]
     Default,
     /// Use a task boundary with optional name and a symbol
     Boundary(Option<Cow<'static, str>>, Symbol),
+    /// Use the error or escalation boundary with the code. A boundary without code catches all codes.
+    Code(Cow<'static, str>, Symbol),
     /// Terminate the process prematurely and have it return the specified error.
     /// Instead of doing this, you should ensure that the BPMN diagram is always modeled
     /// with an error path whenever possible.
@@ -143,6 +178,20 @@ impl Task {
         }
     }
 
+    pub fn error_code<S>(code: S) -> Task
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        Self::Code(code.into(), Symbol::Error)
+    }
+
+    pub fn escalation_code<S>(code: S) -> Task
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        Self::Code(code.into(), Symbol::Escalation)
+    }
+
     pub fn panic<S>(value: S) -> Task
     where
         S: Into<Box<dyn std::error::Error + Send + Sync>>,
@@ -202,3 +251,72 @@ where
         Self::Catch(value.0.into(), value.1)
     }
 }
+
+/// Element of the diagram a function is called for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Element<'a> {
+    /// Bpmn id of the element
+    pub id: &'a str,
+    pub name: Option<&'a str>,
+    pub bpmn_type: BpmnType,
+    /// Symbol of an event, `Symbol::None` for other elements
+    pub symbol: Symbol,
+}
+
+impl<'a> From<&'a Activity> for Element<'a> {
+    fn from(activity: &'a Activity) -> Self {
+        Self {
+            id: activity.id.bpmn(),
+            name: activity.name.as_deref(),
+            bpmn_type: activity.activity_type.into(),
+            symbol: Symbol::None,
+        }
+    }
+}
+
+impl<'a> From<&'a Gateway> for Element<'a> {
+    fn from(gateway: &'a Gateway) -> Self {
+        Self {
+            id: gateway.id.bpmn(),
+            name: gateway.name.as_deref(),
+            bpmn_type: gateway.gateway_type.into(),
+            symbol: Symbol::None,
+        }
+    }
+}
+
+impl<'a> From<&'a Event> for Element<'a> {
+    fn from(event: &'a Event) -> Self {
+        Self {
+            id: event.id.bpmn(),
+            name: event.name.as_deref(),
+            bpmn_type: event.event_type.into(),
+            symbol: event.symbol,
+        }
+    }
+}
+
+/// Result of a function passed through the interceptors registered with
+/// [`ProcessBuilder::intercept`](crate::ProcessBuilder::intercept).
+#[derive(Debug)]
+pub enum CallResult {
+    Task(Task),
+    Exclusive(Exclusive),
+    Inclusive(Inclusive),
+    EventBased(IntermediateEvent),
+    /// Result of an end, intermediate throw or intermediate catch event callback
+    Event(Result<(), Box<dyn std::error::Error + Send + Sync>>),
+    /// Terminate the process and have it return the specified error, whatever the function is.
+    Panic(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// Convenient factory methods
+impl CallResult {
+    pub fn panic<S>(value: S) -> CallResult
+    where
+        S: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        Self::Panic(value.into())
+    }
+}
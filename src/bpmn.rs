@@ -1,6 +1,7 @@
 use crate::{
     diagram::{Id, Outputs, reader::BpmnError},
     process::{DiagramError, RuntimeError},
+    timer::Timer,
 };
 use core::fmt;
 use std::fmt::Display;
@@ -8,6 +9,26 @@ use std::fmt::Display;
 pub(crate) const _DEFINITIONS: &str = "definitions";
 pub(crate) const PROCESS: &str = "process";
 
+// Lanes and human task details
+pub(crate) const LANE: &str = "lane";
+pub(crate) const FLOW_NODE_REF: &str = "flowNodeRef";
+pub(crate) const DOCUMENTATION: &str = "documentation";
+pub(crate) const ASSIGNMENT_DEFINITION: &str = "assignmentDefinition";
+
+// Variable mappings
+pub(crate) const DATA_INPUT_ASSOCIATION: &str = "dataInputAssociation";
+pub(crate) const DATA_OUTPUT_ASSOCIATION: &str = "dataOutputAssociation";
+pub(crate) const DATA_OBJECT_REFERENCE: &str = "dataObjectReference";
+pub(crate) const DATA_STORE_REFERENCE: &str = "dataStoreReference";
+pub(crate) const SOURCE_REF: &str = "sourceRef";
+pub(crate) const TARGET_REF: &str = "targetRef";
+pub(crate) const INPUT: &str = "input";
+pub(crate) const OUTPUT: &str = "output";
+
+// Root elements referenced by event definitions
+pub(crate) const ERROR: &str = "error";
+pub(crate) const ESCALATION: &str = "escalation";
+
 // Event
 pub(crate) const START_EVENT: &str = "startEvent";
 pub(crate) const END_EVENT: &str = "endEvent";
@@ -27,6 +48,11 @@ pub(crate) const SIGNAL_EVENT_DEFINITION: &str = "signalEventDefinition";
 pub(crate) const TERMINATE_EVENT_DEFINITION: &str = "terminateEventDefinition";
 pub(crate) const TIMER_EVENT_DEFINITION: &str = "timerEventDefinition";
 
+// Timer definition
+pub(crate) const TIME_DURATION: &str = "timeDuration";
+pub(crate) const TIME_DATE: &str = "timeDate";
+pub(crate) const TIME_CYCLE: &str = "timeCycle";
+
 // Task
 pub(crate) const TASK: &str = "task";
 pub(crate) const SERVICE_TASK: &str = "serviceTask";
@@ -54,19 +80,27 @@ pub(crate) const INCLUSIVE_GATEWAY: &str = "inclusiveGateway";
 pub(crate) const EVENT_BASED_GATEWAY: &str = "eventBasedGateway";
 
 // Attributes
+pub(crate) const ATTRIB_ASSIGNEE: &str = "assignee";
 pub(crate) const ATTRIB_ID: &str = "id";
 pub(crate) const ATTRIB_IS_EXECUTABLE: &str = "isExecutable";
 pub(crate) const ATTRIB_NAME: &str = "name";
+pub(crate) const ATTRIB_SOURCE: &str = "source";
 pub(crate) const ATTRIB_SOURCE_REF: &str = "sourceRef";
+pub(crate) const ATTRIB_TARGET: &str = "target";
 pub(crate) const ATTRIB_TARGET_REF: &str = "targetRef";
 pub(crate) const ATTRIB_DEFAULT: &str = "default";
 pub(crate) const ATTRIB_EXPORTER_VERSION: &str = "exporterVersion";
 pub(crate) const ATTRIB_ATTACHED_TO_REF: &str = "attachedToRef";
 pub(crate) const ATTRIB_CANCEL_ACTIVITY: &str = "cancelActivity";
+pub(crate) const ATTRIB_TRIGGERED_BY_EVENT: &str = "triggeredByEvent";
+pub(crate) const ATTRIB_ERROR_CODE: &str = "errorCode";
+pub(crate) const ATTRIB_ERROR_REF: &str = "errorRef";
+pub(crate) const ATTRIB_ESCALATION_CODE: &str = "escalationCode";
+pub(crate) const ATTRIB_ESCALATION_REF: &str = "escalationRef";
 
-// All Bpmn types
+/// BPMN element types
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub(crate) enum BpmnType {
+pub enum BpmnType {
     Boundary,
     BusinessRuleTask,
     CallActivity,
@@ -136,15 +170,23 @@ impl From<GatewayType> for BpmnType {
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum Attrib {
+    Assignee,
     AttachedToRef,
     CancelActivity,
     Default,
+    ErrorCode,
+    ErrorRef,
+    EscalationCode,
+    EscalationRef,
     ExporterVersion,
     Id,
     IsExecutable,
     Name,
+    Source,
     SourceRef,
+    Target,
     TargetRef,
+    TriggeredByEvent,
 }
 
 impl TryFrom<&str> for Attrib {
@@ -152,15 +194,23 @@ impl TryFrom<&str> for Attrib {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         Ok(match value {
+            ATTRIB_ASSIGNEE => Attrib::Assignee,
             ATTRIB_ATTACHED_TO_REF => Attrib::AttachedToRef,
             ATTRIB_CANCEL_ACTIVITY => Attrib::CancelActivity,
             ATTRIB_DEFAULT => Attrib::Default,
+            ATTRIB_ERROR_CODE => Attrib::ErrorCode,
+            ATTRIB_ERROR_REF => Attrib::ErrorRef,
+            ATTRIB_ESCALATION_CODE => Attrib::EscalationCode,
+            ATTRIB_ESCALATION_REF => Attrib::EscalationRef,
             ATTRIB_EXPORTER_VERSION => Attrib::ExporterVersion,
             ATTRIB_ID => Attrib::Id,
             ATTRIB_IS_EXECUTABLE => Attrib::IsExecutable,
             ATTRIB_NAME => Attrib::Name,
+            ATTRIB_SOURCE => Attrib::Source,
             ATTRIB_SOURCE_REF => Attrib::SourceRef,
+            ATTRIB_TARGET => Attrib::Target,
             ATTRIB_TARGET_REF => Attrib::TargetRef,
+            ATTRIB_TRIGGERED_BY_EVENT => Attrib::TriggeredByEvent,
             _ => Err(BpmnError::TypeNotImplemented(value.into()))?,
         })
     }
@@ -366,6 +416,9 @@ pub(crate) struct Event {
     pub(crate) name: Option<String>,
     pub(crate) attached_to_ref: Option<Id>,
     pub(crate) outputs: Outputs,
+    pub(crate) timer: Option<Timer>,
+    // Error or escalation code from the referenced error or escalation
+    pub(crate) code: Option<String>,
 }
 
 impl Display for Event {
@@ -409,6 +462,27 @@ pub(crate) struct Activity {
     pub(crate) data_index: Option<usize>,
     pub(crate) name: Option<String>,
     pub(crate) outputs: Outputs,
+    // Event subprocess, started when an event is thrown in the enclosing process
+    pub(crate) triggered_by_event: bool,
+    pub(crate) documentation: Option<String>,
+    // Name of the lane containing the activity
+    pub(crate) lane: Option<String>,
+    pub(crate) assignee: Option<String>,
+    // Variables mapped into and out of the activity
+    pub(crate) mapping: Option<Mapping>,
+}
+
+// Input and output mappings of an activity, as source and target variable names.
+#[derive(Debug, Default)]
+pub(crate) struct Mapping {
+    pub(crate) inputs: Vec<(String, String)>,
+    pub(crate) outputs: Vec<(String, String)>,
+}
+
+impl Mapping {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.inputs.is_empty() && self.outputs.is_empty()
+    }
 }
 
 impl Display for Activity {
@@ -425,9 +499,11 @@ impl Display for Activity {
 impl BpmnValidate for Activity {
     fn validate(&self) -> Result<(), BpmnError> {
         match self {
-            Activity { outputs, .. } if outputs.len() == 0 => {
-                Err(BpmnError::NoOutput(self.to_string()))?
-            }
+            Activity {
+                outputs,
+                triggered_by_event: false,
+                ..
+            } if outputs.len() == 0 => Err(BpmnError::NoOutput(self.to_string()))?,
             _ => Ok(()),
         }
     }
@@ -0,0 +1,323 @@
+use crate::process::lock;
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    sync::{Arc, Condvar, Mutex, PoisonError},
+    time::{Duration, SystemTime},
+};
+
+type Wake = Box<dyn FnOnce() + Send>;
+
+/// Source of time used by the engine when waiting on timer events.
+///
+/// The default is the [`SystemClock`]. Use the [`MockClock`] in tests to fast-forward time.
+pub trait Clock: Send + Sync {
+    /// Current point in time.
+    fn now(&self) -> SystemTime;
+
+    /// Call `wake` once the clock has reached `deadline`. A deadline that has already passed
+    /// should call `wake` as soon as possible. `wake` must not block.
+    fn wake_at(&self, deadline: SystemTime, wake: Box<dyn FnOnce() + Send>) -> WakeId;
+
+    /// Drop a wake up registered with [`Clock::wake_at`] without calling it. Does nothing if it
+    /// has already been called.
+    fn cancel(&self, id: WakeId);
+}
+
+/// Identifies a wake up registered with [`Clock::wake_at`], used to cancel it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WakeId(pub u64);
+
+// Pending wake up ordered by deadline and then by insertion order.
+struct Entry {
+    deadline: SystemTime,
+    sequence: u64,
+    wake: Wake,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        (self.deadline, self.sequence) == (other.deadline, other.sequence)
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.deadline, self.sequence).cmp(&(other.deadline, other.sequence))
+    }
+}
+
+#[derive(Default)]
+struct Entries {
+    queue: BinaryHeap<Reverse<Entry>>,
+    sequence: u64,
+}
+
+impl Entries {
+    fn next_id(&mut self) -> WakeId {
+        self.sequence += 1;
+        WakeId(self.sequence)
+    }
+
+    fn push(&mut self, deadline: SystemTime, wake: Wake) -> WakeId {
+        let id = self.next_id();
+        self.queue.push(Reverse(Entry {
+            deadline,
+            sequence: id.0,
+            wake,
+        }));
+        id
+    }
+
+    fn cancel(&mut self, id: WakeId) {
+        self.queue.retain(|Reverse(entry)| entry.sequence != id.0);
+    }
+
+    // Remove all entries that are due at `now`
+    fn due(&mut self, now: SystemTime) -> Vec<Wake> {
+        let mut result = vec![];
+        while let Some(Reverse(entry)) = self.queue.peek()
+            && entry.deadline <= now
+        {
+            if let Some(Reverse(entry)) = self.queue.pop() {
+                result.push(entry.wake);
+            }
+        }
+        result
+    }
+}
+
+#[derive(Default)]
+struct SystemEntries {
+    entries: Entries,
+    running: bool,
+}
+
+#[derive(Default)]
+struct SystemTimers {
+    entries: Mutex<SystemEntries>,
+    changed: Condvar,
+}
+
+/// Clock using the system time. Wake ups are handled by one background thread per clock that
+/// is started when needed and stops when nothing is waiting.
+#[derive(Clone, Default)]
+pub struct SystemClock {
+    timers: Arc<SystemTimers>,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn run(timers: Arc<SystemTimers>) {
+        let mut guard = lock(&timers.entries);
+        loop {
+            let now = SystemTime::now();
+            let due = guard.entries.due(now);
+            if !due.is_empty() {
+                drop(guard);
+                due.into_iter().for_each(|wake| wake());
+                guard = lock(&timers.entries);
+                continue;
+            }
+
+            let Some(Reverse(next)) = guard.entries.queue.peek() else {
+                // Nothing is waiting, stop the thread.
+                guard.running = false;
+                return;
+            };
+
+            let timeout = next.deadline.duration_since(now).unwrap_or_default();
+            guard = timers
+                .changed
+                .wait_timeout(guard, timeout)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn wake_at(&self, deadline: SystemTime, wake: Box<dyn FnOnce() + Send>) -> WakeId {
+        let mut guard = lock(&self.timers.entries);
+        let id = guard.entries.push(deadline, wake);
+        if guard.running {
+            self.timers.changed.notify_one();
+        } else {
+            guard.running = true;
+            let timers = Arc::clone(&self.timers);
+            std::thread::spawn(move || SystemClock::run(timers));
+        }
+        id
+    }
+
+    fn cancel(&self, id: WakeId) {
+        // The thread stops by itself when nothing is left
+        lock(&self.timers.entries).entries.cancel(id);
+    }
+}
+
+struct MockTime {
+    now: SystemTime,
+    entries: Entries,
+    auto_advance: bool,
+}
+
+/// Clock for tests where time only moves when told to.
+///
+/// ```
+/// use snurr::{Clock, MockClock};
+/// use std::time::{Duration, SystemTime};
+///
+/// let clock = MockClock::new(SystemTime::UNIX_EPOCH);
+/// clock.advance(Duration::from_secs(60));
+/// assert_eq!(clock.now(), SystemTime::UNIX_EPOCH + Duration::from_secs(60));
+/// ```
+#[derive(Clone)]
+pub struct MockClock {
+    time: Arc<Mutex<MockTime>>,
+}
+
+impl MockClock {
+    /// Create a clock that starts at `now`.
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            time: Arc::new(Mutex::new(MockTime {
+                now,
+                entries: Default::default(),
+                auto_advance: false,
+            })),
+        }
+    }
+
+    /// Create a clock that starts at `now` and moves forward to the deadline as soon as
+    /// anything waits on it. Timer events complete immediately while `now` still reflects
+    /// the time waited.
+    pub fn auto_advance(now: SystemTime) -> Self {
+        let clock = Self::new(now);
+        lock(&clock.time).auto_advance = true;
+        clock
+    }
+
+    /// Move time forward and wake everything with a deadline that has been reached.
+    pub fn advance(&self, duration: Duration) {
+        let now = lock(&self.time).now + duration;
+        self.set(now);
+    }
+
+    /// Set the time and wake everything with a deadline that has been reached.
+    /// Time never moves backwards.
+    pub fn set(&self, now: SystemTime) {
+        let due = {
+            let mut time = lock(&self.time);
+            time.now = time.now.max(now);
+            let now = time.now;
+            time.entries.due(now)
+        };
+        due.into_iter().for_each(|wake| wake());
+    }
+
+    /// Number of wake ups that wait for the clock to advance.
+    pub fn pending(&self) -> usize {
+        lock(&self.time).entries.queue.len()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> SystemTime {
+        lock(&self.time).now
+    }
+
+    fn wake_at(&self, deadline: SystemTime, wake: Box<dyn FnOnce() + Send>) -> WakeId {
+        let mut time = lock(&self.time);
+        if time.auto_advance {
+            time.now = time.now.max(deadline);
+        }
+
+        if deadline <= time.now {
+            let id = time.entries.next_id();
+            drop(time);
+            wake();
+            id
+        } else {
+            time.entries.push(deadline, wake)
+        }
+    }
+
+    fn cancel(&self, id: WakeId) {
+        lock(&self.time).entries.cancel(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{sync::mpsc, time::UNIX_EPOCH};
+
+    #[test]
+    fn mock_clock_wakes_in_order() {
+        let clock = MockClock::new(UNIX_EPOCH);
+        let (sender, receiver) = mpsc::channel();
+        for secs in [30, 10, 20] {
+            let sender = sender.clone();
+            clock.wake_at(
+                UNIX_EPOCH + Duration::from_secs(secs),
+                Box::new(move || sender.send(secs).unwrap()),
+            );
+        }
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![10, 20]);
+        assert_eq!(clock.pending(), 1);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![30]);
+    }
+
+    #[test]
+    fn mock_clock_cancel() {
+        let clock = MockClock::new(UNIX_EPOCH);
+        let (sender, receiver) = mpsc::channel();
+        let ids: Vec<_> = [10, 20]
+            .into_iter()
+            .map(|secs| {
+                let sender = sender.clone();
+                clock.wake_at(
+                    UNIX_EPOCH + Duration::from_secs(secs),
+                    Box::new(move || sender.send(secs).unwrap()),
+                )
+            })
+            .collect();
+        clock.cancel(ids[0]);
+        assert_eq!(clock.pending(), 1);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec![20]);
+    }
+
+    #[test]
+    fn system_clock_wakes() {
+        let clock = SystemClock::new();
+        let (sender, receiver) = mpsc::channel();
+        for millis in [20, 1] {
+            let sender = sender.clone();
+            clock.wake_at(
+                clock.now() + Duration::from_millis(millis),
+                Box::new(move || sender.send(millis).unwrap()),
+            );
+        }
+        assert_eq!(receiver.recv().unwrap(), 1);
+        assert_eq!(receiver.recv().unwrap(), 20);
+    }
+}
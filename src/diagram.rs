@@ -2,9 +2,12 @@ mod events;
 pub mod reader;
 
 use crate::{
-    bpmn::{Activity, ActivityType, Bpmn, BpmnType, Event, Gateway, GatewayType, Symbol},
+    api::Element,
+    bpmn::{
+        Activity, ActivityType, Bpmn, BpmnType, Event, EventType, Gateway, GatewayType, Symbol,
+    },
     diagram::events::Events,
-    error::RuntimeError,
+    error::{DiagramError, RuntimeError},
     process::func_map::FuncMap,
 };
 
@@ -17,16 +20,37 @@ use std::{
 pub struct Diagram {
     process_index: usize,
     data: Box<[ProcessData]>,
+    // Names of the signals thrown by intermediate throw and end events
+    thrown_signals: HashSet<String>,
 }
 
 impl Diagram {
     fn new(process_index: usize, data: Box<[ProcessData]>) -> Self {
+        let thrown_signals = data
+            .iter()
+            .flat_map(ProcessData::iter)
+            .filter_map(|bpmn| match bpmn {
+                Bpmn::Event(Event {
+                    event_type: EventType::IntermediateThrow | EventType::End,
+                    symbol: Symbol::Signal,
+                    name: Some(name),
+                    ..
+                }) => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
         Self {
             process_index,
             data,
+            thrown_signals,
         }
     }
 
+    // A signal with the name is thrown somewhere in the process
+    pub fn throws_signal(&self, name: &str) -> bool {
+        self.thrown_signals.contains(name)
+    }
+
     pub fn main_process(&self) -> Result<&ProcessData, RuntimeError> {
         self.get_process(self.process_index)
     }
@@ -42,9 +66,103 @@ impl Diagram {
         &self.data
     }
 
+    // Find an event subprocess in the process with a start event that catches the thrown event.
+    // Matched by code, then by name. An error or escalation start event without code catches all, whatever its name.
+    // Returns the event subprocess data and the index of its start event.
+    pub fn event_subprocess(
+        &self,
+        process: &ProcessData,
+        thrown: &Event,
+    ) -> Option<(&ProcessData, usize)> {
+        let starts: Vec<(&ProcessData, usize, &Event)> = process
+            .event_subprocesses
+            .iter()
+            .filter_map(|index| match process.get(*index) {
+                Some(Bpmn::Activity(Activity {
+                    data_index: Some(data_index),
+                    ..
+                })) => self.get_process(*data_index).ok(),
+                _ => None,
+            })
+            .flat_map(|subprocess| {
+                subprocess
+                    .starts()
+                    .filter(|(_, start)| start.symbol == thrown.symbol)
+                    .map(move |(index, start)| (subprocess, index, start))
+            })
+            .collect();
+
+        let find = |matches: &dyn Fn(&Event) -> bool| {
+            starts
+                .iter()
+                .find(|(_, _, start)| matches(start))
+                .map(|(subprocess, index, _)| (*subprocess, *index))
+        };
+        find(&|start| start.code.is_some() && start.code == thrown.code)
+            .or_else(|| find(&|start| start.name.is_some() && start.name == thrown.name))
+            .or_else(|| {
+                find(&|start| {
+                    start.code.is_none()
+                        && (matches!(thrown.symbol, Symbol::Error | Symbol::Escalation)
+                            || start.name.is_none() && thrown.name.is_none())
+                })
+            })
+    }
+
+    // Indices from the main process to the activity, gateway or event with the name or id.
+    // Every index but the last is a subprocess containing the next one. Elements of the process are
+    // found before elements of its subprocesses. Event subprocesses are not searched.
+    pub fn find_element(&self, search: &str) -> Result<Vec<usize>, RuntimeError> {
+        let mut path = vec![];
+        if self.find_in(self.main_process()?, search, &mut path)? {
+            Ok(path)
+        } else {
+            Err(DiagramError::MissingElement(search.into()))?
+        }
+    }
+
+    fn find_in(
+        &self,
+        process: &ProcessData,
+        search: &str,
+        path: &mut Vec<usize>,
+    ) -> Result<bool, RuntimeError> {
+        if let Some(index) = process.data.iter().position(|bpmn| match bpmn {
+            Bpmn::Event(Event { id, name, .. })
+            | Bpmn::Activity(Activity { id, name, .. })
+            | Bpmn::Gateway(Gateway { id, name, .. }) => {
+                name.as_deref().is_some_and(|name| name == search) || id.bpmn() == search
+            }
+            Bpmn::SequenceFlow { .. } => false,
+        }) {
+            path.push(index);
+            return Ok(true);
+        }
+
+        for (index, bpmn) in process.data.iter().enumerate() {
+            if let Bpmn::Activity(Activity {
+                activity_type: ActivityType::SubProcess,
+                data_index: Some(data_index),
+                triggered_by_event: false,
+                ..
+            }) = bpmn
+            {
+                path.push(index);
+                if self.find_in(self.get_process(*data_index)?, search, path)? {
+                    return Ok(true);
+                }
+                path.pop();
+            }
+        }
+        Ok(false)
+    }
+
     pub fn install_and_check(&mut self, func_map: &FuncMap) -> HashSet<String> {
         let mut missing = HashSet::new();
-        for process_data in self.data.iter_mut() {
+        let scopes = self.scopes();
+        let waiting = self.waiting_gateways();
+        for ((process_data, scopes), waiting) in self.data.iter_mut().zip(scopes).zip(waiting) {
+            let scopes: Vec<_> = scopes.iter().map(String::as_str).collect();
             for bpmn in &mut process_data.data {
                 match bpmn {
                     Bpmn::Activity(Activity {
@@ -54,7 +172,25 @@ impl Diagram {
                         activity_type,
                         ..
                     }) if !matches!(activity_type, ActivityType::SubProcess) => {
-                        if let Some(id) = func_map.get_id(BpmnType::Task, id, name.as_deref()) {
+                        // Functions registered for the activity type before those for any task,
+                        // and matching functions last.
+                        if let Some(id) = func_map
+                            .get_id(
+                                &[(*activity_type).into(), BpmnType::Task],
+                                id,
+                                name.as_deref(),
+                                &scopes,
+                            )
+                            .or_else(|| {
+                                let element = Element {
+                                    id: id.bpmn(),
+                                    name: name.as_deref(),
+                                    bpmn_type: (*activity_type).into(),
+                                    symbol: Symbol::None,
+                                };
+                                func_map.get_matching(&element, &scopes)
+                            })
+                        {
                             func_idx.replace(id);
                         } else {
                             missing.insert(format!(
@@ -75,10 +211,13 @@ impl Diagram {
                         ..
                     }) if outputs.len() > 1 => {
                         if let Some(id) =
-                            func_map.get_id((*gateway_type).into(), id, name.as_deref())
+                            func_map.get_id(&[(*gateway_type).into()], id, name.as_deref(), &scopes)
                         {
                             func_idx.replace(id);
-                        } else {
+                        } else if *gateway_type != GatewayType::EventBased
+                            || !waiting.contains(id.local())
+                        {
+                            // Event-based gateways without a function wait on their events
                             missing.insert(format!(
                                 "{gateway_type}: {}",
                                 name.as_deref().unwrap_or(id.bpmn())
@@ -91,19 +230,152 @@ impl Diagram {
         }
         missing
     }
+
+    // Event-based gateways of each process data that can wait on their events without a function. Every
+    // target must be a named message or signal event, a timer event with a definition or a named receive task.
+    fn waiting_gateways(&self) -> Vec<HashSet<usize>> {
+        let waitable = |bpmn: &Bpmn| match bpmn {
+            Bpmn::Event(Event {
+                event_type: EventType::IntermediateCatch,
+                symbol,
+                name,
+                timer,
+                ..
+            }) => match symbol {
+                Symbol::Message | Symbol::Signal => name.is_some(),
+                Symbol::Timer => timer.is_some(),
+                _ => false,
+            },
+            Bpmn::Activity(Activity {
+                activity_type: ActivityType::ReceiveTask,
+                name,
+                ..
+            }) => name.is_some(),
+            _ => false,
+        };
+
+        self.data
+            .iter()
+            .map(|process_data| {
+                process_data
+                    .iter()
+                    .filter_map(|bpmn| match bpmn {
+                        Bpmn::Gateway(Gateway {
+                            gateway_type: GatewayType::EventBased,
+                            id,
+                            outputs,
+                            ..
+                        }) if process_data.targets(outputs).all(waitable) => Some(*id.local()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Names and ids of the subprocesses enclosing each process data, innermost first.
+    fn scopes(&self) -> Vec<Vec<String>> {
+        let mut parents = vec![None; self.data.len()];
+        for (index, process_data) in self.data.iter().enumerate() {
+            for bpmn in process_data.iter() {
+                if let Bpmn::Activity(Activity {
+                    id,
+                    name,
+                    data_index: Some(data_index),
+                    ..
+                }) = bpmn
+                    && let Some(parent) = parents.get_mut(*data_index)
+                {
+                    *parent = Some((index, id.bpmn(), name.as_deref()));
+                }
+            }
+        }
+
+        (0..self.data.len())
+            .map(|mut index| {
+                let mut scopes = vec![];
+                while let Some((parent, id, name)) = parents[index] {
+                    scopes.extend([Some(id), name].into_iter().flatten().map(String::from));
+                    index = parent;
+                }
+                scopes
+            })
+            .collect()
+    }
+
+    // Flows of a gateway choice that are not outgoing flows of the gateways using the callback.
+    pub fn unknown_flows(&self, func_idx: usize, flows: &[&str]) -> Vec<String> {
+        let mut unknown = vec![];
+        for process_data in self.data.iter() {
+            for bpmn in process_data.iter() {
+                if let Bpmn::Gateway(gateway @ Gateway { outputs, .. }) = bpmn
+                    && gateway.func_idx == Some(func_idx)
+                {
+                    unknown.extend(
+                        flows
+                            .iter()
+                            .filter(|flow| process_data.find_by_name_or_id(flow, outputs).is_none())
+                            .map(|flow| format!("{gateway}: {flow}")),
+                    );
+                }
+            }
+        }
+        unknown
+    }
 }
 
 #[derive(Default, Debug)]
 pub struct ProcessData {
-    // Start event in the process
-    start: usize,
+    // Start event without symbol in the process
+    start: Option<usize>,
+    // All start events in the process
+    starts: Box<[usize]>,
+    // Event subprocesses in the process
+    event_subprocesses: Box<[usize]>,
     data: Box<[Bpmn]>,
     pub events: Events,
 }
 
 impl ProcessData {
-    pub fn start(&self) -> usize {
+    pub fn start(&self) -> Result<usize, DiagramError> {
         self.start
+            .ok_or_else(|| DiagramError::MissingStartEvent(Symbol::None.to_string()))
+    }
+
+    fn starts(&self) -> impl Iterator<Item = (usize, &Event)> {
+        self.starts
+            .iter()
+            .filter_map(|index| match self.get(*index) {
+                Some(Bpmn::Event(event)) => Some((*index, event)),
+                _ => None,
+            })
+    }
+
+    pub fn timer_starts(&self) -> impl Iterator<Item = (usize, &Event)> {
+        self.starts()
+            .filter(|(_, event)| event.symbol == Symbol::Timer && event.timer.is_some())
+    }
+
+    // Find start event by name or id
+    pub fn find_start(&self, search: &str) -> Result<usize, DiagramError> {
+        self.starts()
+            .find(|(_, Event { id, name, .. })| {
+                name.as_deref().is_some_and(|name| name == search) || id.bpmn() == search
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| DiagramError::MissingStartEvent(search.into()))
+    }
+
+    // Find message start event by name
+    pub fn find_message_start(&self, message: &str) -> Result<usize, DiagramError> {
+        self.starts()
+            .find(|(_, Event { symbol, name, .. })| {
+                *symbol == Symbol::Message && name.as_deref().is_some_and(|name| name == message)
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| {
+                DiagramError::MissingStartEvent(format!("{} {message}", Symbol::Message))
+            })
     }
 
     pub fn get(&self, index: usize) -> Option<&Bpmn> {
@@ -123,6 +395,14 @@ impl ProcessData {
         })
     }
 
+    // Elements targeted by the sequence flows.
+    pub fn targets<'a>(&'a self, outputs: &'a Outputs) -> impl Iterator<Item = &'a Bpmn> {
+        outputs.iter().filter_map(|index| match self.get(*index) {
+            Some(Bpmn::SequenceFlow { target_ref, .. }) => self.get(*target_ref.local()),
+            _ => None,
+        })
+    }
+
     pub fn find_by_intermediate_event<'a>(
         &'a self,
         name: &str,
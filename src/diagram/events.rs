@@ -2,10 +2,12 @@ use crate::{
     bpmn::{Event, EventType, Symbol},
     diagram::Id,
     process::{DiagramError, RuntimeError},
+    timer::Timer,
 };
 use std::{
     borrow::{Borrow, Cow},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    time::SystemTime,
 };
 
 #[derive(Debug, PartialEq, Eq, Hash)]
@@ -29,7 +31,15 @@ impl<'short, 'long: 'short> Borrow<BoundaryKey<'short>> for BoundaryKeyWrap<'lon
 #[derive(Default, Debug)]
 pub struct Events {
     boundaries: HashMap<BoundaryKeyWrap<'static>, usize>,
+    // Error and escalation boundaries with a code per activity
+    boundary_codes: HashMap<(usize, Symbol), HashMap<String, usize>>,
+    // Error and escalation boundary without a code per activity, that catches all
+    catch_all: HashMap<(usize, Symbol), usize>,
     catch_event_links: HashMap<String, usize>,
+    // Timer boundaries with a timer definition per activity
+    timeouts: HashMap<usize, Vec<(usize, Timer)>>,
+    // Activities with a signal boundary
+    signal_boundaries: HashSet<usize>,
 }
 
 impl Events {
@@ -40,8 +50,39 @@ impl Events {
                 attached_to_ref: Some(attached_to_ref),
                 symbol,
                 name,
+                timer,
+                code,
                 ..
             } => {
+                if let (Symbol::Timer, Some(timer)) = (symbol, timer) {
+                    self.timeouts
+                        .entry(*attached_to_ref.local())
+                        .or_default()
+                        .push((*id.local(), timer.clone()));
+                }
+
+                if *symbol == Symbol::Signal {
+                    self.signal_boundaries.insert(*attached_to_ref.local());
+                }
+
+                if code.is_none() && matches!(symbol, Symbol::Error | Symbol::Escalation) {
+                    self.catch_all
+                        .entry((*attached_to_ref.local(), *symbol))
+                        .or_insert(*id.local());
+                }
+
+                if let Some(code) = code {
+                    self.boundary_codes
+                        .entry((*attached_to_ref.local(), *symbol))
+                        .or_default()
+                        .insert(code.clone(), *id.local());
+
+                    // Without a name, it can only be matched by its code
+                    if name.is_none() {
+                        return;
+                    }
+                }
+
                 self.boundaries.insert(
                     BoundaryKeyWrap(BoundaryKey {
                         id: *attached_to_ref.local(),
@@ -64,12 +105,40 @@ impl Events {
         }
     }
 
-    pub(crate) fn boundary(&self, id: &Id, symbol: Symbol, name: Option<&str>) -> Option<&usize> {
-        self.boundaries.get(&BoundaryKey {
-            id: *id.local(),
-            symbol,
-            name: name.map(Cow::Borrowed),
-        })
+    // Find boundary by code, then by name. An error or escalation boundary without code catches all
+    // codes, whatever its name. Names must match.
+    pub(crate) fn boundary(
+        &self,
+        id: &Id,
+        symbol: Symbol,
+        name: Option<&str>,
+        code: Option<&str>,
+    ) -> Option<&usize> {
+        let by_name = |name: Option<&str>| {
+            self.boundaries.get(&BoundaryKey {
+                id: *id.local(),
+                symbol,
+                name: name.map(Cow::Borrowed),
+            })
+        };
+
+        code.and_then(|code| self.boundary_codes.get(&(*id.local(), symbol))?.get(code))
+            .or_else(|| by_name(name))
+            .or_else(|| code.and_then(|_| self.catch_all.get(&(*id.local(), symbol))))
+    }
+
+    pub(crate) fn has_signal_boundary(&self, id: &Id) -> bool {
+        self.signal_boundaries.contains(id.local())
+    }
+
+    // The timer boundary attached to the activity that fires first if started at `now`.
+    // Returns the boundary index and the deadline.
+    pub(crate) fn timeout(&self, id: &Id, now: SystemTime) -> Option<(usize, SystemTime)> {
+        self.timeouts
+            .get(id.local())?
+            .iter()
+            .filter_map(|(index, timer)| Some((*index, timer.deadline(now)?)))
+            .min_by_key(|(_, deadline)| *deadline)
     }
 
     // Fetch all boundaries in the process. Used by scaffold only.
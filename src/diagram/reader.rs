@@ -54,16 +54,30 @@ pub fn read_bpmn<R: BufRead>(mut reader: Reader<R>) -> Result<Diagram, ParseErro
                 bpmn_type @ (PROCESS | SUB_PROCESS | TRANSACTION) => {
                     builder.add_new_process(RawData::new(bpmn_type, collect_attributes(&bs)));
                 }
+                // Timer definitions have child elements
+                bpmn_type @ TIMER_EVENT_DEFINITION => {
+                    builder.update_symbol(bpmn_type)?;
+                }
+                ERROR | ESCALATION => builder.add_code(collect_attributes(&bs)),
+                LANE => builder.start_lane(collect_attributes(&bs)),
+                bpmn_type @ (DATA_INPUT_ASSOCIATION | DATA_OUTPUT_ASSOCIATION) => {
+                    builder.start_association(bpmn_type)
+                }
+                DATA_OBJECT_REFERENCE | DATA_STORE_REFERENCE => {
+                    builder.add_data_reference(collect_attributes(&bs))
+                }
                 _ => {}
             },
             Ok(Event::Empty(bs)) => {
                 match bs.local_name().as_ref() {
                     // Attach symbol to parent
+                    bpmn_type @ (ERROR_EVENT_DEFINITION | ESCALATION_EVENT_DEFINITION) => {
+                        builder.update_symbol(bpmn_type)?;
+                        builder.add_reference_to_parent(collect_attributes(&bs));
+                    }
                     bpmn_type @ (CANCEL_EVENT_DEFINITION
                     | COMPENSATE_EVENT_DEFINITION
                     | CONDITIONAL_EVENT_DEFINITION
-                    | ERROR_EVENT_DEFINITION
-                    | ESCALATION_EVENT_DEFINITION
                     | MESSAGE_EVENT_DEFINITION
                     | LINK_EVENT_DEFINITION
                     | SIGNAL_EVENT_DEFINITION
@@ -74,11 +88,29 @@ pub fn read_bpmn<R: BufRead>(mut reader: Reader<R>) -> Result<Diagram, ParseErro
                     bpmn_type @ SEQUENCE_FLOW => {
                         builder.add_to_process(RawData::new(bpmn_type, collect_attributes(&bs)))?;
                     }
+                    ERROR | ESCALATION => builder.add_code(collect_attributes(&bs)),
+                    ASSIGNMENT_DEFINITION => {
+                        builder.add_assignee_to_parent(collect_attributes(&bs))
+                    }
+                    bpmn_type @ (INPUT | OUTPUT) => {
+                        builder.add_mapping_to_parent(bpmn_type, collect_attributes(&bs))
+                    }
+                    DATA_OBJECT_REFERENCE | DATA_STORE_REFERENCE => {
+                        builder.add_data_reference(collect_attributes(&bs))
+                    }
                     _ => {}
                 }
             }
             Ok(Event::End(be)) => match be.local_name().as_ref() {
                 bpmn_type @ (OUTGOING | INCOMING) => builder.add_text_to_parent(bpmn_type),
+                TIME_DURATION => builder.add_timer_to_parent(TIME_DURATION),
+                TIME_DATE => builder.add_timer_to_parent(TIME_DATE),
+                TIME_CYCLE => builder.add_timer_to_parent(TIME_CYCLE),
+                DOCUMENTATION => builder.add_documentation_to_parent(),
+                FLOW_NODE_REF => builder.add_to_lane(),
+                LANE => builder.end_lane(),
+                bpmn_type @ (SOURCE_REF | TARGET_REF) => builder.add_association_ref(bpmn_type),
+                DATA_INPUT_ASSOCIATION | DATA_OUTPUT_ASSOCIATION => builder.end_association(),
                 START_EVENT
                 | END_EVENT
                 | BOUNDARY_EVENT
@@ -156,6 +188,12 @@ struct RawData {
     data_index: Option<usize>,
     outputs: Vec<String>,
     inputs: Vec<String>,
+    // Timer definition type and its value
+    timer: Option<(&'static str, String)>,
+    // Referenced error or escalation id
+    reference: Option<String>,
+    documentation: Option<String>,
+    mapping: Mapping,
 }
 
 impl RawData {
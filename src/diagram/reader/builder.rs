@@ -9,6 +9,7 @@ use crate::{
     bpmn::{Event, *},
     diagram::{Diagram, Id, Outputs, ProcessData, events::Events, reader::RawData},
     process::{ParseError, ParseErrorKind},
+    timer::Timer,
 };
 
 //
@@ -35,6 +36,21 @@ pub(super) struct DataBuilder {
 
     // Temporary text from XML
     text: Option<String>,
+
+    // Error and escalation codes by id
+    codes: HashMap<String, String>,
+
+    // Names of the lanes being read, nested lanes last
+    lanes: Vec<Option<String>>,
+    // Lane name by element id
+    lane_refs: HashMap<String, String>,
+
+    // Data association being read. True for an input association, with the referenced data.
+    association: Option<(bool, Option<String>)>,
+    // Data referenced by the associations of an activity, by activity id. True for inputs.
+    associations: HashMap<String, Vec<(bool, String)>>,
+    // Data object and data store names by reference id
+    data_names: HashMap<String, String>,
 }
 
 impl DataBuilder {
@@ -72,6 +88,124 @@ impl DataBuilder {
         }
     }
 
+    pub(super) fn add_timer_to_parent(&mut self, bpmn_type: &'static str) {
+        if let Some(parent) = self.stack.last_mut()
+            && let Some(text) = self.text.take()
+            && !text.trim().is_empty()
+        {
+            parent.timer = Some((bpmn_type, text));
+        }
+    }
+
+    pub(super) fn add_documentation_to_parent(&mut self) {
+        if let Some(parent) = self.stack.last_mut()
+            && let Some(text) = self.text.take()
+        {
+            parent.documentation = Some(text.trim().to_string());
+        }
+    }
+
+    // Assignee from an assignment definition in the extension elements of a user task.
+    pub(super) fn add_assignee_to_parent(&mut self, mut attributes: HashMap<Attrib, String>) {
+        if let Some(parent) = self.stack.last_mut()
+            && let Some(assignee) = attributes.remove(&Attrib::Assignee)
+        {
+            parent.attributes.insert(Attrib::Assignee, assignee);
+        }
+    }
+
+    // Input or output mapping in the extension elements of an activity.
+    pub(super) fn add_mapping_to_parent(
+        &mut self,
+        bpmn_type: &str,
+        mut attributes: HashMap<Attrib, String>,
+    ) {
+        if let Some(parent) = self.stack.last_mut()
+            && let Some(source) = attributes.remove(&Attrib::Source)
+            && let Some(target) = attributes.remove(&Attrib::Target)
+        {
+            // Expressions are not evaluated. A source is the name of a variable.
+            let source = source.trim_start_matches('=').trim().to_string();
+            match bpmn_type {
+                INPUT => parent.mapping.inputs.push((source, target)),
+                _ => parent.mapping.outputs.push((source, target)),
+            }
+        }
+    }
+
+    pub(super) fn start_association(&mut self, bpmn_type: &str) {
+        self.association = Some((bpmn_type == DATA_INPUT_ASSOCIATION, None));
+    }
+
+    // An input association reads its source and an output association writes its target.
+    pub(super) fn add_association_ref(&mut self, bpmn_type: &str) {
+        if let Some((input, reference)) = &mut self.association
+            && *input == (bpmn_type == SOURCE_REF)
+            && let Some(text) = self.text.take()
+        {
+            *reference = Some(text.trim().to_string());
+        }
+    }
+
+    // Associations are resolved to data names when all is read.
+    pub(super) fn end_association(&mut self) {
+        if let Some((input, Some(reference))) = self.association.take()
+            && let Some(id) = self
+                .stack
+                .last()
+                .and_then(|parent| parent.attributes.get(&Attrib::Id))
+        {
+            self.associations
+                .entry(id.clone())
+                .or_default()
+                .push((input, reference));
+        }
+    }
+
+    pub(super) fn add_data_reference(&mut self, mut attributes: HashMap<Attrib, String>) {
+        if let Some(id) = attributes.remove(&Attrib::Id)
+            && let Some(name) = attributes.remove(&Attrib::Name)
+        {
+            self.data_names.insert(id, name);
+        }
+    }
+
+    pub(super) fn start_lane(&mut self, mut attributes: HashMap<Attrib, String>) {
+        self.lanes.push(attributes.remove(&Attrib::Name));
+    }
+
+    // Elements belong to the innermost lane. Lanes are resolved when all is read.
+    pub(super) fn add_to_lane(&mut self) {
+        if let Some(Some(lane)) = self.lanes.last()
+            && let Some(id) = self.text.take()
+        {
+            self.lane_refs.insert(id.trim().to_string(), lane.clone());
+        }
+    }
+
+    pub(super) fn end_lane(&mut self) {
+        self.lanes.pop();
+    }
+
+    pub(super) fn add_reference_to_parent(&mut self, mut attributes: HashMap<Attrib, String>) {
+        if let Some(parent) = self.stack.last_mut() {
+            parent.reference = attributes
+                .remove(&Attrib::ErrorRef)
+                .or_else(|| attributes.remove(&Attrib::EscalationRef));
+        }
+    }
+
+    // Error and escalation elements are placed outside the process. Codes are resolved when all is read.
+    pub(super) fn add_code(&mut self, mut attributes: HashMap<Attrib, String>) {
+        if let Some(id) = attributes.remove(&Attrib::Id)
+            && let Some(code) = attributes
+                .remove(&Attrib::ErrorCode)
+                .or_else(|| attributes.remove(&Attrib::EscalationCode))
+        {
+            self.codes.insert(id, code);
+        }
+    }
+
     pub(super) fn add_text(&mut self, value: impl Into<String>) {
         self.text.replace(value.into());
     }
@@ -120,7 +254,13 @@ impl DataBuilder {
 impl TryFrom<DataBuilder> for Diagram {
     type Error = ParseErrorKind;
 
-    fn try_from(builder: DataBuilder) -> Result<Self, Self::Error> {
+    fn try_from(mut builder: DataBuilder) -> Result<Self, Self::Error> {
+        for process_data in builder.data.iter_mut() {
+            register_events(process_data, &builder.codes);
+            assign_lanes(process_data, &builder.lane_refs);
+            assign_associations(process_data, &builder.associations, &builder.data_names);
+        }
+
         Ok(Diagram::new(
             builder
                 .process_index
@@ -149,6 +289,8 @@ fn check_unsupported(raw_data: &RawData) -> Result<(), ParseErrorKind> {
 #[derive(Default, Debug)]
 struct ProcessConstruction {
     start: Option<usize>,
+    starts: Vec<usize>,
+    event_subprocesses: Vec<usize>,
     data: Vec<Bpmn>,
     events: Events,
 }
@@ -158,12 +300,24 @@ impl ProcessConstruction {
         let len = self.data.len();
         if let Bpmn::Event(Event {
             event_type: EventType::Start,
-            symbol: Symbol::None,
+            symbol,
+            ..
+        }) = bpmn
+        {
+            if symbol == Symbol::None && self.start.replace(len).is_some() {
+                Err(ParseErrorKind::NotSupported(
+                    "multiple start event without symbol".into(),
+                ))?
+            }
+            self.starts.push(len);
+        }
+
+        if let Bpmn::Activity(Activity {
+            triggered_by_event: true,
             ..
         }) = bpmn
-            && self.start.replace(len).is_some()
         {
-            Err(ParseErrorKind::NotSupported("multiple start event".into()))?
+            self.event_subprocesses.push(len);
         }
 
         bpmn.update_local_id(len);
@@ -188,8 +342,6 @@ impl ProcessConstruction {
                 if let Some(attached_to_ref) = &mut event.attached_to_ref {
                     attached_to_ref.update_local_id(&bpmn_index);
                 }
-
-                self.events.register(event);
             }
             Bpmn::Gateway(Gateway {
                 default, outputs, ..
@@ -204,18 +356,71 @@ impl ProcessConstruction {
     }
 }
 
+// Replace error and escalation references with their codes and register the events.
+fn register_events(process_data: &mut ProcessData, codes: &HashMap<String, String>) {
+    for bpmn in process_data.data.iter_mut() {
+        if let Bpmn::Event(event) = bpmn {
+            event.code = event
+                .code
+                .take()
+                .and_then(|reference| codes.get(&reference).cloned());
+            process_data.events.register(event);
+        }
+    }
+}
+
+fn assign_lanes(process_data: &mut ProcessData, lane_refs: &HashMap<String, String>) {
+    for bpmn in process_data.data.iter_mut() {
+        if let Bpmn::Activity(activity) = bpmn {
+            activity.lane = lane_refs.get(activity.id.bpmn()).cloned();
+        }
+    }
+}
+
+// Data associations map the named data object or data store to a variable with the same name.
+fn assign_associations(
+    process_data: &mut ProcessData,
+    associations: &HashMap<String, Vec<(bool, String)>>,
+    data_names: &HashMap<String, String>,
+) {
+    for bpmn in process_data.data.iter_mut() {
+        if let Bpmn::Activity(activity) = bpmn
+            && let Some(associations) = associations.get(activity.id.bpmn())
+        {
+            for (input, name) in associations
+                .iter()
+                .filter_map(|(input, reference)| Some((input, data_names.get(reference)?)))
+            {
+                let mapping = activity.mapping.get_or_insert_default();
+                match input {
+                    true => mapping.inputs.push((name.clone(), name.clone())),
+                    false => mapping.outputs.push((name.clone(), name.clone())),
+                }
+            }
+        }
+    }
+}
+
 impl TryFrom<ProcessConstruction> for ProcessData {
     type Error = ParseErrorKind;
 
     fn try_from(
         ProcessConstruction {
             start,
+            starts,
+            event_subprocesses,
             data,
             events,
         }: ProcessConstruction,
     ) -> Result<Self, Self::Error> {
+        if starts.is_empty() {
+            Err(ParseErrorKind::MissingStartEvent)?
+        }
+
         Ok(ProcessData {
-            start: start.ok_or(ParseErrorKind::MissingStartEvent)?,
+            start,
+            starts: starts.into_boxed_slice(),
+            event_subprocesses: event_subprocesses.into_boxed_slice(),
             data: data.into_boxed_slice(),
             events,
         })
@@ -233,6 +438,10 @@ impl TryFrom<RawData> for Bpmn {
             outputs,
             inputs,
             data_index,
+            timer,
+            reference,
+            documentation,
+            mapping,
         }: RawData,
     ) -> Result<Self, Self::Error> {
         let bpmn_type: &str = bpmn_type.as_ref();
@@ -254,6 +463,11 @@ impl TryFrom<RawData> for Bpmn {
                 name,
                 attached_to_ref: attributes.remove(&Attrib::AttachedToRef).map(Into::into),
                 outputs: Outputs::new(outputs),
+                timer: timer
+                    .map(|(bpmn_type, value)| Timer::parse(bpmn_type, &value))
+                    .transpose()?,
+                // Resolved to a code when the diagram is complete
+                code: reference,
             }),
             TASK | SCRIPT_TASK | USER_TASK | SERVICE_TASK | CALL_ACTIVITY | RECEIVE_TASK
             | SEND_TASK | MANUAL_TASK | BUSINESS_RULE_TASK | SUB_PROCESS | TRANSACTION => {
@@ -264,6 +478,13 @@ impl TryFrom<RawData> for Bpmn {
                     data_index,
                     name,
                     outputs: Outputs::new(outputs),
+                    triggered_by_event: attributes
+                        .remove(&Attrib::TriggeredByEvent)
+                        .is_some_and(|value| value == "true"),
+                    documentation,
+                    lane: None,
+                    assignee: attributes.remove(&Attrib::Assignee),
+                    mapping: (!mapping.is_empty()).then_some(mapping),
                 })
             }
             EXCLUSIVE_GATEWAY | PARALLEL_GATEWAY | INCLUSIVE_GATEWAY | EVENT_BASED_GATEWAY => {
@@ -301,6 +522,7 @@ pub enum BpmnError {
     NoOutput(String),
     BpmnRequirement(String),
     TypeNotImplemented(String),
+    InvalidTimer(String),
 }
 
 impl Display for BpmnError {
@@ -313,6 +535,7 @@ impl Display for BpmnError {
             BpmnError::NoOutput(s) => write!(f, "{s} has no output"),
             BpmnError::BpmnRequirement(s) => write!(f, "{s}"),
             BpmnError::TypeNotImplemented(s) => write!(f, "tag `{s}` not implemented"),
+            BpmnError::InvalidTimer(s) => write!(f, "invalid timer definition `{s}`"),
         }
     }
 }
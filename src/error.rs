@@ -1,9 +1,9 @@
 use std::fmt::{self, Display, Formatter};
 
-pub use crate::diagram::reader::{BpmnError};
+pub use crate::diagram::reader::BpmnError;
 pub use crate::process::{
-    BpmnFileError, BpmnFileErrorKind, BuildError, DiagramError, ParseError, ParseErrorKind,
-    RuntimeError,
+    BpmnFileError, BpmnFileErrorKind, BuildError, DiagramError, ElementError, ParseError,
+    ParseErrorKind, RuntimeError,
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -2,10 +2,23 @@
 
 mod api;
 mod bpmn;
+mod clock;
 mod diagram;
 pub mod error;
 mod process;
+mod timer;
 
-pub use api::{Exclusive, Inclusive, IntermediateEvent, Task};
-pub use bpmn::Symbol;
-pub use process::{Process, ProcessBuilder};
+pub use api::{CallResult, Element, Exclusive, FlowChoice, Inclusive, IntermediateEvent, Task};
+pub use bpmn::{BpmnType, Symbol};
+pub use clock::{Clock, MockClock, SystemClock, WakeId};
+pub use process::{
+    Process, ProcessBuilder,
+    controller::{ControlStatus, Controller, TokenPosition},
+    inbox::InboxItem,
+    jobs::Job,
+    outcome::{EndEvent, Outcome},
+    registration::{ProcessHandlers, Registration},
+    runtime::{Correlation, Engine, InstanceInfo, InstanceStatus, Runtime},
+    scheduler::{Schedule, Scheduler},
+    variables::{Value, Variables},
+};
@@ -1,28 +1,60 @@
+mod choice;
+mod codegen;
+pub(crate) mod controller;
 mod engine;
+mod fallback;
 pub mod func_map;
 pub(crate) mod handler;
+pub(crate) mod inbox;
+mod intercept;
+pub(crate) mod jobs;
+mod mailbox;
+mod mutable;
+mod namespace;
+pub(crate) mod outcome;
+pub(crate) mod registration;
+pub(crate) mod runtime;
 mod scaffold;
+pub(crate) mod scheduler;
+pub(crate) mod variables;
 
 use crate::{
     Symbol,
-    api::{Exclusive, Inclusive, IntermediateEvent, Task},
+    api::{Element, Exclusive, Inclusive, IntermediateEvent, Task},
     bpmn::BpmnType,
+    clock::{Clock, SystemClock},
     diagram::{
         Diagram,
         reader::{BpmnError, read_bpmn},
     },
-    process::{func_map::FuncMap, handler::Callback},
+    process::{
+        func_map::FuncMap,
+        handler::{Branches, Callback},
+    },
 };
+use controller::Controller;
 use core::fmt;
-use engine::ExecuteInput;
+use engine::{Data, ExecuteInput};
 use handler::Handler;
+use mailbox::Mailbox;
+use outcome::{EndEvent, Outcome};
 use std::{
+    any::Any,
+    convert::Infallible,
     error::Error,
     fmt::{Display, Formatter},
     path::Path,
     str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
 };
 
+// Lock a mutex and recover the data from a poisoned one. User code is never called while the
+// crate's own locks are held, and a panicking callback fails its run, so the data is not used
+// half updated.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// Process builder that contains information from the BPMN file and registered functions
 pub struct ProcessBuilder<T>
 where
@@ -34,6 +66,9 @@ where
     end_callback: Option<usize>,
     intermediate_throw_callback: Option<usize>,
     intermediate_catch_callback: Option<usize>,
+    clock: Arc<dyn Clock>,
+    // Flows of the gateway choices by callback index, checked when built
+    choices: Vec<(usize, Vec<&'static str>)>,
 }
 
 impl<T> ProcessBuilder<T> {
@@ -66,6 +101,8 @@ impl<T> ProcessBuilder<T> {
             end_callback: Default::default(),
             intermediate_throw_callback: Default::default(),
             intermediate_catch_callback: Default::default(),
+            clock: Arc::new(SystemClock::new()),
+            choices: Default::default(),
         })
     }
 
@@ -122,14 +159,98 @@ impl<T> ProcessBuilder<T> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn task<F>(mut self, name: impl Into<String>, func: F) -> Self
+    pub fn task<F>(self, name: impl Into<String>, func: F) -> Self
     where
         F: Fn(&T) -> Task + 'static + Sync + Send,
+    {
+        self.try_task(name, move |data| Ok::<_, Infallible>(func(data)))
+    }
+
+    /// Register a task like [`ProcessBuilder::task`] with a function that can fail, so that `?` can be used.
+    /// The error is returned in a `RuntimeError::Panic` as an [`ElementError`] with the task, or follows a
+    /// boundary selected with [`ProcessBuilder::error_boundary`].
+    ///
+    /// ```rust no_run
+    /// # use snurr::{ProcessBuilder, Task};
+    /// # use std::num::ParseIntError;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<String>::new("dummy.bpmn")?
+    /// .try_task("name or id", |input| {
+    ///     let count: u32 = input.parse()?;
+    ///     println!("{count}");
+    ///     Ok::<_, ParseIntError>(Task::Default)
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_task<F, E>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&T) -> Result<Task, E> + 'static + Sync + Send,
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        self.func_map.insert(
+            BpmnType::Task,
+            name.into(),
+            self.handler
+                .add_callback(Callback::Task(Box::new(move |data| {
+                    func(data).map_err(Into::into)
+                }))),
+        );
+        self
+    }
+
+    /// # External task
+    ///
+    /// The task is handled by workers instead of a function. When an instance started by an `Engine` reaches
+    /// the task, a job is published with a snapshot of the data and the instance waits until a worker has
+    /// completed or failed the job. The topic of the job is the task name.
+    ///
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # use std::sync::Mutex;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<Mutex<u32>>::new("dummy.bpmn")?
+    /// .external_task("name or id", |input| {
+    ///     *input.lock().unwrap()
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn external_task<F, D>(mut self, name: impl Into<String>, snapshot: F) -> Self
+    where
+        F: Fn(&T) -> D + 'static + Sync + Send,
+        D: Any + Send + Sync,
     {
         self.func_map.insert(
             BpmnType::Task,
             name.into(),
-            self.handler.add_callback(Callback::Task(Box::new(func))),
+            self.handler
+                .add_callback(Callback::External(Box::new(move |data| {
+                    Arc::new(snapshot(data))
+                }))),
+        );
+        self
+    }
+
+    /// # Human task
+    ///
+    /// The task is completed by people instead of a function, usually a user task. When an instance started by
+    /// an `Engine` reaches the task, an item is added to the inbox of the engine with the documentation, lane
+    /// and assignee of the task from the diagram. The instance waits until the item is completed.
+    ///
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+    /// .human_task("name or id");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn human_task(mut self, name: impl Into<String>) -> Self {
+        self.func_map.insert(
+            BpmnType::Task,
+            name.into(),
+            self.handler.add_callback(Callback::Human),
         );
         self
     }
@@ -163,15 +284,27 @@ impl<T> ProcessBuilder<T> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn exclusive<F>(mut self, name: impl Into<String>, func: F) -> Self
+    pub fn exclusive<F>(self, name: impl Into<String>, func: F) -> Self
     where
         F: Fn(&T) -> Exclusive + 'static + Sync + Send,
+    {
+        self.try_exclusive(name, move |data| Ok::<_, Infallible>(func(data)))
+    }
+
+    /// Register an exclusive gateway like [`ProcessBuilder::exclusive`] with a function that can fail. The error
+    /// is returned in a `RuntimeError::Panic` as an [`ElementError`] with the gateway.
+    pub fn try_exclusive<F, E>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&T) -> Result<Exclusive, E> + 'static + Sync + Send,
+        E: Into<Box<dyn Error + Send + Sync>>,
     {
         self.func_map.insert(
             BpmnType::Exclusive,
             name.into(),
             self.handler
-                .add_callback(Callback::Exclusive(Box::new(func))),
+                .add_callback(Callback::Exclusive(Box::new(move |data| {
+                    func(data).map_err(Into::into)
+                }))),
         );
         self
     }
@@ -220,15 +353,27 @@ impl<T> ProcessBuilder<T> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn inclusive<F>(mut self, name: impl Into<String>, func: F) -> Self
+    pub fn inclusive<F>(self, name: impl Into<String>, func: F) -> Self
     where
         F: Fn(&T) -> Inclusive + 'static + Sync + Send,
+    {
+        self.try_inclusive(name, move |data| Ok::<_, Infallible>(func(data)))
+    }
+
+    /// Register an inclusive gateway like [`ProcessBuilder::inclusive`] with a function that can fail. The error
+    /// is returned in a `RuntimeError::Panic` as an [`ElementError`] with the gateway.
+    pub fn try_inclusive<F, E>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&T) -> Result<Inclusive, E> + 'static + Sync + Send,
+        E: Into<Box<dyn Error + Send + Sync>>,
     {
         self.func_map.insert(
             BpmnType::Inclusive,
             name.into(),
             self.handler
-                .add_callback(Callback::Inclusive(Box::new(func))),
+                .add_callback(Callback::Inclusive(Box::new(move |data| {
+                    func(data).map_err(Into::into)
+                }))),
         );
         self
     }
@@ -238,6 +383,8 @@ impl<T> ProcessBuilder<T> {
     /// An event-based gateway waits for an incoming event, after which the outgoing flow is selected based
     /// on the name and symbol of the catching intermediate event. Event-based gateways require at least two outgoing flows.
     ///
+    /// The function is optional. Without it, the gateway waits until the first of its events arrives.
+    ///
     /// ## One flow
     ///
     /// ```rust no_run
@@ -250,19 +397,53 @@ impl<T> ProcessBuilder<T> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn event_based<F>(mut self, name: impl Into<String>, func: F) -> Self
+    pub fn event_based<F>(self, name: impl Into<String>, func: F) -> Self
     where
         F: Fn(&T) -> IntermediateEvent + 'static + Sync + Send,
+    {
+        self.try_event_based(name, move |data| Ok::<_, Infallible>(func(data)))
+    }
+
+    /// Register an event-based gateway like [`ProcessBuilder::event_based`] with a function that can fail. The
+    /// error is returned in a `RuntimeError::Panic` as an [`ElementError`] with the gateway.
+    pub fn try_event_based<F, E>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&T) -> Result<IntermediateEvent, E> + 'static + Sync + Send,
+        E: Into<Box<dyn Error + Send + Sync>>,
     {
         self.func_map.insert(
             BpmnType::EventBased,
             name.into(),
             self.handler
-                .add_callback(Callback::EventBased(Box::new(func))),
+                .add_callback(Callback::EventBased(Box::new(move |data| {
+                    func(data).map_err(Into::into)
+                }))),
         );
         self
     }
 
+    /// Select a boundary for the errors of fallible task functions, registered with [`ProcessBuilder::try_task`].
+    /// Return `None` to terminate the process with the error in a `RuntimeError::Panic`.
+    ///
+    /// ```rust no_run
+    /// # use snurr::{ProcessBuilder, Symbol};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+    /// .error_boundary(|error| {
+    ///     println!("{error}");
+    ///     Some(Symbol::Error.into())
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn error_boundary<F>(mut self, func: F) -> Self
+    where
+        F: Fn(&ElementError) -> Option<Task> + 'static + Sync + Send,
+    {
+        self.handler.set_error_boundary(Box::new(func));
+        self
+    }
+
     /// Optionally register an end callback to act on end events. If an error is returned it terminate the process
     /// prematurely and have it return the specified error. Only one can be registered.
     /// ```rust no_run
@@ -325,15 +506,15 @@ impl<T> ProcessBuilder<T> {
 
     /// Optionally register an intermediate catch callback to act on intermediate catch events. If an error is returned it terminate the process
     /// prematurely and have it return the specified error. Only one can be registered.
+    ///
+    /// A timer catch event with a timer definition is called after the engine has waited on the timer.
     /// ```rust no_run
     /// # use snurr::{ProcessBuilder, Symbol};
-    /// # use std::time::{Duration};
     /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
     /// .intermediate_catch_event(|_input, name, symbol| {
     ///     match (name, symbol) {
-    ///         (Some("wait 5 sec"), Symbol::Timer) => std::thread::sleep(Duration::new(5, 0)),
-    ///         (Some("wait 1 minute"), Symbol::Timer) => std::thread::sleep(Duration::new(60, 0)),
+    ///         (Some("wait 5 sec"), Symbol::Timer) => println!("waited 5 seconds"),
     ///         _ => println!("ignore other catch events"),
     ///     }
     ///     Ok(())
@@ -355,23 +536,82 @@ impl<T> ProcessBuilder<T> {
         self
     }
 
+    /// # Branch-local data
+    ///
+    /// By default all tokens share the process data. With branch-local data, every token forked by a
+    /// gateway or a task with several outputs gets its own data returned by `fork`, for example a clone or
+    /// the part of the data the branch works on. When the tokens are joined, `merge` is called with the
+    /// name or id of the joining gateway and the data of the branches, and the token continuing
+    /// after the join gets the merged data. Branches that end without a join are merged when the last one
+    /// ends, with the name or id of that end event.
+    ///
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # use std::sync::atomic::{AtomicU32, Ordering::Relaxed};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<AtomicU32>::new("dummy.bpmn")?
+    /// .branch_data(
+    ///     |input| AtomicU32::new(input.load(Relaxed)),
+    ///     |_gateway, branches| AtomicU32::new(branches.iter().map(|b| b.load(Relaxed)).sum()),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn branch_data<F, M>(mut self, fork: F, merge: M) -> Self
+    where
+        F: Fn(&T) -> T + 'static + Sync + Send,
+        M: Fn(&str, Vec<T>) -> T + 'static + Sync + Send,
+    {
+        self.handler.set_branches(Branches {
+            fork: Box::new(fork),
+            merge: Box::new(merge),
+        });
+        self
+    }
+
+    /// Use another clock than the system clock when waiting on timer events. Use a `MockClock` in tests
+    /// to fast-forward time.
+    /// ```rust no_run
+    /// # use snurr::{MockClock, ProcessBuilder};
+    /// # use std::time::SystemTime;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+    /// .clock(MockClock::auto_advance(SystemTime::now()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
     /// Install and check that all required functions have been registered. Return runnable process if successful.
-    /// If `build` returns an error, it contains the missing functions.
+    /// If `build` returns an error, it contains the missing functions or the gateway choices without a matching flow.
     pub fn build(mut self) -> Result<Process<T>, BuildError> {
         let result = self.diagram.install_and_check(&self.func_map);
-        if result.is_empty() {
-            Ok(Process {
-                diagram: self.diagram,
-                handler: self.handler,
-                end_callback: self.end_callback,
-                intermediate_throw_callback: self.intermediate_throw_callback,
-                intermediate_catch_callback: self.intermediate_catch_callback,
-            })
-        } else {
+        if !result.is_empty() {
             Err(BuildError::MissingImplementations(
                 result.into_iter().collect::<Vec<_>>().join(", "),
-            ))
+            ))?
+        }
+
+        let unknown: Vec<_> = self
+            .choices
+            .iter()
+            .flat_map(|(index, flows)| self.diagram.unknown_flows(*index, flows))
+            .collect();
+        if !unknown.is_empty() {
+            Err(BuildError::UnknownFlows(unknown.join(", ")))?
         }
+
+        Ok(Process {
+            diagram: self.diagram,
+            handler: self.handler,
+            end_callback: self.end_callback,
+            intermediate_throw_callback: self.intermediate_throw_callback,
+            intermediate_catch_callback: self.intermediate_catch_callback,
+            clock: self.clock,
+        })
     }
 }
 
@@ -397,6 +637,8 @@ impl<T> FromStr for ProcessBuilder<T> {
             end_callback: Default::default(),
             intermediate_throw_callback: Default::default(),
             intermediate_catch_callback: Default::default(),
+            clock: Arc::new(SystemClock::new()),
+            choices: Default::default(),
         })
     }
 }
@@ -411,6 +653,7 @@ where
     end_callback: Option<usize>,
     intermediate_throw_callback: Option<usize>,
     intermediate_catch_callback: Option<usize>,
+    clock: Arc<dyn Clock>,
 }
 
 impl<T> Process<T> {
@@ -451,12 +694,168 @@ impl<T> Process<T> {
     where
         T: Send + Sync,
     {
-        self.execute(ExecuteInput::new(
-            self.diagram.main_process()?,
-            false,
-            &data,
-        ))?;
-        Ok(data)
+        self.run_with_outcome(data).map(|(data, _)| data)
+    }
+
+    /// Run the process and return the `T` together with the `Outcome`, listing the end events reached.
+    /// ```rust no_run
+    /// # use snurr::{ProcessBuilder, Symbol};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let bpmn = ProcessBuilder::<()>::new("dummy.bpmn")?.build()?;
+    /// let (_data, outcome) = bpmn.run_with_outcome(())?;
+    /// if outcome.reached_symbol(Symbol::Error) {
+    ///     println!("process failed");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_with_outcome(&self, data: T) -> Result<(T, Outcome), RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        self.run_at(self.diagram.main_process()?.start()?, data)
+    }
+
+    /// Run the process from the start event with the given name or id. Use it when the process has
+    /// several start events. Returns a `DiagramError::MissingStartEvent` if no start event matched.
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let bpmn = ProcessBuilder::<()>::new("dummy.bpmn")?.build()?;
+    /// bpmn.run_from_start("Manual start", ())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_from_start(&self, name_or_id: &str, data: T) -> Result<T, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        self.run_at(self.diagram.main_process()?.find_start(name_or_id)?, data)
+            .map(|(data, _)| data)
+    }
+
+    /// Run the process from the message start event named after the message.
+    /// Returns a `DiagramError::MissingStartEvent` if no message start event matched.
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let bpmn = ProcessBuilder::<()>::new("dummy.bpmn")?.build()?;
+    /// bpmn.run_on_message("Order received", ())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_on_message(&self, message_name: &str, data: T) -> Result<T, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        self.run_at(
+            self.diagram
+                .main_process()?
+                .find_message_start(message_name)?,
+            data,
+        )
+        .map(|(data, _)| data)
+    }
+
+    /// Run the process with a controller that can pause and resume it from another thread,
+    /// see [`Controller`].
+    pub fn run_with_controller(&self, data: T, controller: &Controller) -> Result<T, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        self.run_instance(
+            self.diagram.main_process()?.start()?,
+            data,
+            &Mailbox::default(),
+            Some(controller),
+        )
+        .map(|(data, _)| data)
+    }
+
+    /// Run the process from the activity, gateway or event with the given name or id, in the main
+    /// process or in a subprocess. Elements before it are not executed. A subprocess entered this
+    /// way continues with the enclosing process when it completes.
+    /// Returns a `DiagramError::MissingElement` if no element matched.
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let bpmn = ProcessBuilder::<()>::new("dummy.bpmn")?.build()?;
+    /// // Replay a failed instance from the task that failed
+    /// bpmn.run_from("Send invoice", ())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn run_from(&self, element_id_or_name: &str, data: T) -> Result<T, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        let path = self.diagram.find_element(element_id_or_name)?;
+        let (start, entry) = path
+            .split_first()
+            .ok_or_else(|| DiagramError::MissingElement(element_id_or_name.into()))?;
+        self.run_path(*start, entry, data, &Mailbox::default(), None)
+            .map(|(data, _)| data)
+    }
+
+    // Run the main process from the start event with index `start`.
+    pub(crate) fn run_at(&self, start: usize, data: T) -> Result<(T, Outcome), RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        self.run_instance(start, data, &Mailbox::default(), None)
+    }
+
+    // Run the main process with a mailbox that can receive messages from outside the instance.
+    fn run_instance(
+        &self,
+        start: usize,
+        data: T,
+        mailbox: &Mailbox<T>,
+        controller: Option<&Controller>,
+    ) -> Result<(T, Outcome), RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        self.run_path(start, &[], data, mailbox, controller)
+    }
+
+    // Run the main process from the element with index `start`. A subprocess at `start` is entered at
+    // the elements in `entry`, one index for each nested subprocess.
+    fn run_path(
+        &self,
+        start: usize,
+        entry: &[usize],
+        data: T,
+        mailbox: &Mailbox<T>,
+        controller: Option<&Controller>,
+    ) -> Result<(T, Outcome), RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        // Tasks with a timer or signal boundary might still run when the process has ended.
+        let (outcome, merged) = std::thread::scope(|scope| {
+            let completed = self.execute(
+                start,
+                ExecuteInput::new(
+                    self.diagram.main_process()?,
+                    &data,
+                    scope,
+                    mailbox,
+                    controller,
+                    entry,
+                ),
+            )?;
+
+            // With branch-local data, the process ends with the data merged from the branches
+            let merged = match (completed.data, self.handler.branches()) {
+                (Some(data @ Data::Branch(_)), Some(branches)) => {
+                    Some(data.into_owned(&branches.fork))
+                }
+                _ => None,
+            };
+            Ok::<_, RuntimeError>((Outcome::new(&completed.end_events), merged))
+        })?;
+        Ok((merged.unwrap_or(data), outcome))
     }
 }
 
@@ -564,6 +963,20 @@ pub enum RuntimeError {
     Engine(String),
     /// User triggered a Panic inside some of the process steps with the attached error
     Panic(Box<dyn std::error::Error + Send + Sync>),
+    /// Cancel, error, escalation or signal end event not caught by any boundary or event subprocess
+    Uncaught(EndEvent),
+    /// All tokens wait on events that can not arrive anymore. Contains the waiting elements.
+    Stalled(Vec<String>),
+    /// No running instance with the id or key
+    UnknownInstance(String),
+    /// No process deployed under the key
+    UnknownProcess(String),
+    /// No job with the id locked by the worker
+    UnknownJob(usize),
+    /// No user task with the id in the inbox
+    UnknownUserTask(usize),
+    /// User task with the id not assigned to the user
+    NotAssigned(usize, String),
 }
 
 impl Display for RuntimeError {
@@ -572,6 +985,25 @@ impl Display for RuntimeError {
             RuntimeError::Diagram(..) => f.write_str("diagram error"),
             RuntimeError::Engine(s) => write!(f, "engine failure `{s}`"),
             RuntimeError::Panic(..) => f.write_str("user triggered panic"),
+            RuntimeError::Uncaught(EndEvent {
+                id, name, symbol, ..
+            }) => write!(
+                f,
+                "uncaught {symbol} end event `{}`",
+                name.as_deref().unwrap_or(id)
+            ),
+            RuntimeError::Stalled(waiting) => {
+                write!(f, "process stalled waiting on `{}`", waiting.join("`, `"))
+            }
+            RuntimeError::UnknownInstance(instance) => {
+                write!(f, "no running instance `{instance}`")
+            }
+            RuntimeError::UnknownProcess(key) => write!(f, "no process deployed as `{key}`"),
+            RuntimeError::UnknownJob(id) => write!(f, "no job `{id}` locked by the worker"),
+            RuntimeError::UnknownUserTask(id) => write!(f, "no user task `{id}` in the inbox"),
+            RuntimeError::NotAssigned(id, user) => {
+                write!(f, "user task `{id}` not assigned to `{user}`")
+            }
         }
     }
 }
@@ -596,6 +1028,8 @@ pub enum DiagramError {
     MissingIntermediateThrowEventName(String),
     MissingIntermediateCatchEvent(String, String),
     MissingEndEvent,
+    MissingStartEvent(String),
+    MissingElement(String),
     NotSupported(String),
     BpmnRequirement(String),
 }
@@ -628,6 +1062,8 @@ impl Display for DiagramError {
                 )
             }
             DiagramError::MissingEndEvent => f.write_str("missing end event"),
+            DiagramError::MissingStartEvent(s) => write!(f, "missing start event {s}"),
+            DiagramError::MissingElement(s) => write!(f, "missing element {s}"),
             DiagramError::NotSupported(s) => {
                 write!(f, "{s} not supported")
             }
@@ -650,18 +1086,62 @@ impl From<DiagramError> for RuntimeError {
 #[derive(Debug)]
 pub enum BuildError {
     MissingImplementations(String),
+    UnknownFlows(String),
 }
 
 impl Display for BuildError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             BuildError::MissingImplementations(s) => write!(f, "missing implementations {s}"),
+            BuildError::UnknownFlows(s) => write!(f, "unknown flows {s}"),
         }
     }
 }
 
 impl Error for BuildError {}
 
+/// Error returned by a fallible task or gateway function, with the element that returned it. The process
+/// returns it in a `RuntimeError::Panic`, unless it is mapped to a boundary with
+/// [`ProcessBuilder::error_boundary`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct ElementError {
+    /// Bpmn id of the element
+    pub id: String,
+    pub name: Option<String>,
+    pub bpmn_type: BpmnType,
+    pub error: Box<dyn Error + Send + Sync>,
+}
+
+impl ElementError {
+    pub(crate) fn new(element: &Element, error: Box<dyn Error + Send + Sync>) -> Self {
+        Self {
+            id: element.id.into(),
+            name: element.name.map(Into::into),
+            bpmn_type: element.bpmn_type,
+            error,
+        }
+    }
+}
+
+impl Display for ElementError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} `{}`: {}",
+            self.bpmn_type,
+            self.name.as_deref().unwrap_or(&self.id),
+            self.error
+        )
+    }
+}
+
+impl Error for ElementError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.error.as_ref())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,63 @@
+use super::{ProcessBuilder, handler::Callback};
+use crate::{
+    api::{Exclusive, FlowChoice, Inclusive},
+    bpmn::BpmnType,
+};
+use std::borrow::Cow;
+
+impl<T> ProcessBuilder<T> {
+    /// Register an exclusive gateway like [`ProcessBuilder::exclusive`] that returns a [`FlowChoice`].
+    /// `build` returns an error if a choice has no matching outgoing flow.
+    ///
+    /// ```rust no_run
+    /// # use snurr::{FlowChoice, ProcessBuilder};
+    /// # #[derive(Clone, Copy)]
+    /// # enum Equal { Yes, No }
+    /// # impl FlowChoice for Equal {
+    /// #     const ALL: &'static [Self] = &[Equal::Yes, Equal::No];
+    /// #     fn flow(&self) -> &'static str { match self { Equal::Yes => "YES", Equal::No => "NO" } }
+    /// # }
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+    /// .exclusive_choice("name or id", |input| Equal::Yes);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exclusive_choice<C, F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        C: FlowChoice,
+        F: Fn(&T) -> C + 'static + Sync + Send,
+    {
+        let index = self
+            .handler
+            .add_callback(Callback::Exclusive(Box::new(move |data| {
+                Ok(Exclusive::Flow(Cow::Borrowed(func(data).flow())))
+            })));
+        self.func_map
+            .insert(BpmnType::Exclusive, name.into(), index);
+        self.choices.push((index, flows::<C>()));
+        self
+    }
+
+    /// Register an inclusive gateway like [`ProcessBuilder::inclusive`] that returns the [`FlowChoice`]s to
+    /// run. No choice selects the default flow. `build` returns an error if a choice has no matching outgoing flow.
+    pub fn inclusive_choice<C, F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        C: FlowChoice,
+        F: Fn(&T) -> Vec<C> + 'static + Sync + Send,
+    {
+        let index = self
+            .handler
+            .add_callback(Callback::Inclusive(Box::new(move |data| {
+                Ok(Inclusive::fork(func(data).iter().map(FlowChoice::flow)))
+            })));
+        self.func_map
+            .insert(BpmnType::Inclusive, name.into(), index);
+        self.choices.push((index, flows::<C>()));
+        self
+    }
+}
+
+fn flows<C: FlowChoice>() -> Vec<&'static str> {
+    C::ALL.iter().map(FlowChoice::flow).collect()
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    ProcessBuilder,
+    bpmn::{Activity, ActivityType, Bpmn, Gateway, GatewayType, Symbol},
+};
+
+impl<T> ProcessBuilder<T> {
+    /// Generate a `Handlers<T>` trait with one method per task and exclusive or inclusive gateway, a
+    /// [`FlowChoice`](crate::FlowChoice) enum with the outgoing flows of each gateway and a `register`
+    /// function that installs the handlers on a builder. A missing handler or a misspelled flow is then a
+    /// compile error. Call it from a build script and include the generated file. Event-based
+    /// gateways are not part of the trait and wait on their events.
+    ///
+    /// Returns an error if two names map to the same identifier, for example names that only
+    /// differ in non-ASCII characters.
+    ///
+    /// `build.rs`
+    /// ```no_run
+    /// use snurr::ProcessBuilder;
+    ///
+    /// fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     println!("cargo::rerun-if-changed=examples/counter.bpmn");
+    ///     let out = std::path::Path::new(&std::env::var("OUT_DIR")?).join("counter.rs");
+    ///     ProcessBuilder::<()>::new("examples/counter.bpmn")?.codegen(out)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// `main.rs`
+    /// ```ignore
+    /// mod counter {
+    ///     include!(concat!(env!("OUT_DIR"), "/counter.rs"));
+    /// }
+    /// ```
+    pub fn codegen(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut codegen = Codegen::default();
+        for process in self.diagram.data() {
+            let mut boundaries = process.events.boundaries();
+            for bpmn in process.iter() {
+                match bpmn {
+                    Bpmn::Activity(
+                        activity @ Activity {
+                            activity_type, id, ..
+                        },
+                    ) if *activity_type != ActivityType::SubProcess => {
+                        codegen
+                            .add_task(activity, boundaries.remove(id.local()).unwrap_or_default());
+                    }
+                    Bpmn::Gateway(
+                        gateway @ Gateway {
+                            gateway_type: GatewayType::Exclusive | GatewayType::Inclusive,
+                            outputs,
+                            ..
+                        },
+                    ) if outputs.len() > 1 => {
+                        let flows = outputs
+                            .iter()
+                            .filter_map(|index| match process.get(*index) {
+                                Some(Bpmn::SequenceFlow { id, name, .. }) => {
+                                    Some(name.as_deref().unwrap_or(id.bpmn()))
+                                }
+                                _ => None,
+                            })
+                            .collect();
+                        codegen.add_gateway(gateway, flows);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        std::fs::write(path, codegen.create()?)
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+    "pub", "ref", "return", "self", "static", "struct", "super", "trait", "true", "try", "type",
+    "unsafe", "use", "where", "while", "yield",
+];
+
+// Words of a name, split at everything that is not alphanumeric.
+fn words(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+fn snake_case(value: &str) -> String {
+    let ident = words(value)
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("_");
+    match ident.chars().next() {
+        None => "element".into(),
+        Some(c) if c.is_ascii_digit() => format!("_{ident}"),
+        _ if KEYWORDS.contains(&ident.as_str()) => format!("{ident}_"),
+        _ => ident,
+    }
+}
+
+// Upper case words are capitalized, other words keep their case.
+fn camel_case(value: &str, prefix: &str) -> String {
+    let ident: String = words(value)
+        .map(|word| {
+            let rest = &word[1..];
+            let rest = if rest.chars().all(|c| !c.is_ascii_lowercase()) {
+                rest.to_ascii_lowercase()
+            } else {
+                rest.to_string()
+            };
+            format!("{}{rest}", word[..1].to_ascii_uppercase())
+        })
+        .collect();
+    match ident.chars().next() {
+        Some(c) if !c.is_ascii_digit() => ident,
+        _ => format!("{prefix}{ident}"),
+    }
+}
+
+// Identifier for the name, or an error if another name already maps to it.
+fn unique<'a>(
+    ident: String,
+    name: &'a str,
+    taken: &mut HashMap<String, &'a str>,
+) -> Result<String, Error> {
+    match taken.get(&ident) {
+        Some(other) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("`{other}` and `{name}` both map to the identifier `{ident}`"),
+        )),
+        None => {
+            taken.insert(ident.clone(), name);
+            Ok(ident)
+        }
+    }
+}
+
+fn name_or_id(Activity { id, name, .. }: &Activity) -> &str {
+    name.as_deref().unwrap_or(id.bpmn())
+}
+
+// Escape a name for a string literal
+fn literal(value: &str) -> String {
+    format!("{value:?}")
+}
+
+#[derive(Debug)]
+struct Task<'a> {
+    activity: &'a Activity,
+    symbols: Vec<(Option<String>, Symbol)>,
+}
+
+#[derive(Debug)]
+struct GatewayInner<'a> {
+    gateway: &'a Gateway,
+    flows: Vec<&'a str>,
+}
+
+#[derive(Debug, Default)]
+struct Codegen<'a> {
+    tasks: Vec<Task<'a>>,
+    gateways: Vec<GatewayInner<'a>>,
+}
+
+impl<'a> Codegen<'a> {
+    fn add_task(&mut self, activity: &'a Activity, symbols: Vec<(Option<String>, Symbol)>) {
+        match self
+            .tasks
+            .iter_mut()
+            .find(|task| name_or_id(task.activity) == name_or_id(activity))
+        {
+            Some(task) => task.symbols.extend(symbols),
+            None => self.tasks.push(Task { activity, symbols }),
+        }
+    }
+
+    // Gateways with the same name share one handler, with the flows of all of them.
+    fn add_gateway(&mut self, gateway: &'a Gateway, flows: Vec<&'a str>) {
+        match self.gateways.iter_mut().find(|inner| {
+            inner.gateway.gateway_type == gateway.gateway_type
+                && inner
+                    .gateway
+                    .name
+                    .as_deref()
+                    .unwrap_or(inner.gateway.id.bpmn())
+                    == gateway.name.as_deref().unwrap_or(gateway.id.bpmn())
+        }) {
+            Some(inner) => {
+                for flow in flows {
+                    if !inner.flows.contains(&flow) {
+                        inner.flows.push(flow);
+                    }
+                }
+            }
+            None => self.gateways.push(GatewayInner { gateway, flows }),
+        }
+    }
+
+    fn create(&self) -> Result<String, Error> {
+        let mut methods = HashMap::new();
+        let mut types = HashMap::new();
+        let mut handlers = vec![];
+        let mut enums = vec![];
+        let mut registrations = vec![];
+
+        for Task { activity, symbols } in &self.tasks {
+            let name_or_id = name_or_id(activity);
+            let method = unique(snake_case(name_or_id), name_or_id, &mut methods)?;
+            let mut handler = format!("    /// {} `{name_or_id}`\n", activity.activity_type);
+            if !symbols.is_empty() {
+                handler.push_str(&format!(
+                    "    ///\n    /// Boundaries: {}\n",
+                    symbols
+                        .iter()
+                        .map(|v| format!("{v:?}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            handler.push_str(&format!(
+                "    fn {method}(&self, input: &T) -> snurr::Task;"
+            ));
+            handlers.push(handler);
+            registrations.push(registration(
+                "task",
+                name_or_id,
+                &format!("handlers.{method}(input)"),
+            ));
+        }
+
+        for GatewayInner { gateway, flows } in &self.gateways {
+            let name_or_id = gateway.name.as_deref().unwrap_or(gateway.id.bpmn());
+            let method = unique(snake_case(name_or_id), name_or_id, &mut methods)?;
+            let ty = unique(camel_case(name_or_id, "Gateway"), name_or_id, &mut types)?;
+            let mut handler = format!("    /// {} `{name_or_id}`\n", gateway.gateway_type);
+            match gateway.gateway_type {
+                GatewayType::Inclusive => {
+                    handler.push_str(&format!(
+                        "    ///\n    /// No flow selects the default flow.\n    fn {method}(&self, input: &T) -> Vec<{ty}>;"
+                    ));
+                    registrations.push(registration(
+                        "inclusive_choice",
+                        name_or_id,
+                        &format!("handlers.{method}(input)"),
+                    ));
+                }
+                _ => {
+                    handler.push_str(&format!("    fn {method}(&self, input: &T) -> {ty};"));
+                    registrations.push(registration(
+                        "exclusive_choice",
+                        name_or_id,
+                        &format!("handlers.{method}(input)"),
+                    ));
+                }
+            }
+            handlers.push(handler);
+
+            let mut taken = HashMap::new();
+            let variants = flows
+                .iter()
+                .map(|flow| Ok((unique(camel_case(flow, "Flow"), flow, &mut taken)?, *flow)))
+                .collect::<Result<Vec<_>, Error>>()?;
+            enums.push(format!(
+                "/// Outgoing flows of {} `{name_or_id}`\n#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]\npub enum {ty} {{\n{}}}\n",
+                gateway.gateway_type,
+                variants
+                    .iter()
+                    .map(|(variant, _)| format!("    {variant},\n"))
+                    .collect::<String>()
+            ));
+            enums.push(format!(
+                "impl snurr::FlowChoice for {ty} {{\n    const ALL: &'static [Self] = &[{}];\n\n    fn flow(&self) -> &'static str {{\n        match self {{\n{}        }}\n    }}\n}}\n",
+                variants
+                    .iter()
+                    .map(|(variant, _)| format!("{ty}::{variant}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                variants
+                    .iter()
+                    .map(|(variant, flow)| format!(
+                        "            {ty}::{variant} => {},\n",
+                        literal(flow)
+                    ))
+                    .collect::<String>()
+            ));
+        }
+
+        let mut content = vec![
+            "// Generated by snurr. Do not edit.\n".to_string(),
+            format!(
+                "/// Handlers for the tasks and gateways of the process\npub trait Handlers<T> {{\n{}\n}}\n",
+                handlers.join("\n\n")
+            ),
+        ];
+        content.extend(enums);
+        content.push(format!(
+            "/// Register all handlers on the process builder\npub fn register<T, H>(builder: snurr::ProcessBuilder<T>, handlers: H) -> snurr::ProcessBuilder<T>\nwhere\n    H: Handlers<T> + Send + Sync + 'static,\n{{\n    let handlers = std::sync::Arc::new(handlers);\n    builder\n{}\n}}\n",
+            registrations.join("\n")
+        ));
+        Ok(content.join("\n"))
+    }
+}
+
+// Register the handler method, sharing the handlers between the closures
+fn registration(register: &str, name_or_id: &str, call: &str) -> String {
+    format!(
+        "        .{register}({}, {{\n            let handlers = std::sync::Arc::clone(&handlers);\n            move |input| {call}\n        }})",
+        literal(name_or_id)
+    )
+}
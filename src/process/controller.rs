@@ -0,0 +1,275 @@
+use super::lock;
+use std::{
+    collections::BTreeMap,
+    ops::{Deref, DerefMut},
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+};
+
+/// Position of a token in a running process instance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TokenPosition {
+    /// Name or id of the element the token continues with, or waits at
+    pub element: String,
+    /// Name or id of the element the token executed last
+    pub last_executed: Option<String>,
+    /// The token waits on a timer, signal, message, job or user task
+    pub waiting: bool,
+}
+
+/// Status of a process instance returned by [`Controller::status`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ControlStatus {
+    pub paused: bool,
+    /// Tokens executing or waiting on events, in the order they were created
+    pub tokens: Vec<TokenPosition>,
+}
+
+#[derive(Default)]
+struct State {
+    paused: bool,
+    // Incremented on every change, to wake `wait_until`
+    generation: usize,
+    next_id: usize,
+    tokens: BTreeMap<usize, TokenPosition>,
+    // Waiting tokens by the execution loop of the process or subprocess
+    waiting: BTreeMap<usize, Vec<TokenPosition>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+/// Handle to pause and resume a running process instance and to query the position of its tokens.
+///
+/// A paused instance stops between steps. Tokens finish the element they are executing and wait
+/// before continuing with the next one. Timers, signals and messages still arrive while paused,
+/// but a token that catches one stops at the caught element before its function runs.
+///
+/// ```rust no_run
+/// use snurr::{Controller, ProcessBuilder};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let process = ProcessBuilder::<()>::new("dummy.bpmn")?.build()?;
+///     let controller = Controller::new();
+///
+///     std::thread::scope(|scope| {
+///         let handle = scope.spawn(|| process.run_with_controller((), &controller));
+///         controller.pause();
+///         for token in controller.status().tokens {
+///             println!("at {} after {:?}", token.element, token.last_executed);
+///         }
+///         controller.resume();
+///         handle.join().unwrap()
+///     })?;
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Controller {
+    inner: Arc<Inner>,
+}
+
+impl Controller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.inner.state)
+    }
+
+    // Lock the state to change it. Waiters are woken when the guard is dropped.
+    fn change(&self) -> Changed<'_> {
+        let mut state = self.lock();
+        state.generation += 1;
+        Changed {
+            state,
+            changed: &self.inner.changed,
+        }
+    }
+
+    /// Stop the instance before its next step.
+    pub fn pause(&self) {
+        self.change().paused = true;
+    }
+
+    /// Continue a paused instance.
+    pub fn resume(&self) {
+        self.change().paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused
+    }
+
+    /// Paused state and the position of the tokens.
+    pub fn status(&self) -> ControlStatus {
+        Self::status_of(&self.lock())
+    }
+
+    /// Block until the status matches the condition and return it, for example until a token
+    /// waits at an element. The condition is checked again whenever the status changes.
+    pub fn wait_until(&self, mut condition: impl FnMut(&ControlStatus) -> bool) -> ControlStatus {
+        loop {
+            let (status, generation) = {
+                let state = self.lock();
+                (Self::status_of(&state), state.generation)
+            };
+            if condition(&status) {
+                return status;
+            }
+            let mut state = self.lock();
+            while state.generation == generation {
+                state = self
+                    .inner
+                    .changed
+                    .wait(state)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+        }
+    }
+
+    fn status_of(state: &State) -> ControlStatus {
+        let mut tokens: Vec<_> = state
+            .tokens
+            .iter()
+            .chain(
+                state
+                    .waiting
+                    .iter()
+                    .flat_map(|(id, waiting)| waiting.iter().map(move |position| (id, position))),
+            )
+            .collect();
+        tokens.sort_by_key(|(id, _)| **id);
+        ControlStatus {
+            paused: state.paused,
+            tokens: tokens
+                .into_iter()
+                .map(|(_, position)| position.clone())
+                .collect(),
+        }
+    }
+
+    // Track a token until the guard is dropped. `last` is the element the token continues from.
+    pub(super) fn token(&self, last: Option<&str>) -> Token<'_> {
+        let mut state = self.lock();
+        state.next_id += 1;
+        Token {
+            controller: self,
+            id: state.next_id,
+            current: last.map(Into::into),
+            last_executed: None,
+        }
+    }
+
+    // Track the tokens waiting in an execution loop until the guard is dropped.
+    pub(super) fn waiting(&self) -> Waiting<'_> {
+        let mut state = self.lock();
+        state.next_id += 1;
+        Waiting {
+            controller: self,
+            id: state.next_id,
+        }
+    }
+}
+
+// State locked for a change, that wakes the waiters when dropped.
+struct Changed<'a> {
+    state: MutexGuard<'a, State>,
+    changed: &'a Condvar,
+}
+
+impl Deref for Changed<'_> {
+    type Target = State;
+
+    fn deref(&self) -> &State {
+        &self.state
+    }
+}
+
+impl DerefMut for Changed<'_> {
+    fn deref_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+}
+
+impl Drop for Changed<'_> {
+    fn drop(&mut self) {
+        self.changed.notify_all();
+    }
+}
+
+// Token executing elements, removed from the status when dropped.
+pub(super) struct Token<'a> {
+    controller: &'a Controller,
+    id: usize,
+    current: Option<String>,
+    last_executed: Option<String>,
+}
+
+impl Token<'_> {
+    // Move the token to the element, blocking while the instance is paused.
+    pub(super) fn step(&mut self, element: &str) {
+        self.last_executed = self.current.replace(element.to_string());
+        self.controller.change().tokens.insert(
+            self.id,
+            TokenPosition {
+                element: element.to_string(),
+                last_executed: self.last_executed.clone(),
+                waiting: false,
+            },
+        );
+        let mut state = self.controller.lock();
+        while state.paused {
+            state = self
+                .controller
+                .inner
+                .changed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    // The element executed before the current one.
+    pub(super) fn last_executed(&self) -> Option<&str> {
+        self.last_executed.as_deref()
+    }
+}
+
+impl Drop for Token<'_> {
+    fn drop(&mut self) {
+        self.controller.change().tokens.remove(&self.id);
+    }
+}
+
+// Tokens waiting in an execution loop, removed from the status when dropped.
+pub(super) struct Waiting<'a> {
+    controller: &'a Controller,
+    id: usize,
+}
+
+impl Waiting<'_> {
+    pub(super) fn update(&self, tokens: Vec<TokenPosition>) {
+        self.controller.change().waiting.insert(self.id, tokens);
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.controller.change().waiting.remove(&self.id);
+    }
+}
+
+impl TokenPosition {
+    pub(super) fn waiting(element: &str, last_executed: Option<&str>) -> Self {
+        Self {
+            element: element.to_string(),
+            last_executed: last_executed.map(Into::into),
+            waiting: true,
+        }
+    }
+}
@@ -5,11 +5,30 @@ use crate::{
     api::{Exclusive, Inclusive, Task},
     bpmn::{Activity, ActivityType, Bpmn, Event, EventType, Gateway, GatewayType, Symbol},
     diagram::{Outputs, ProcessData},
-    process::{DiagramError, RuntimeError},
+    process::{
+        DiagramError, RuntimeError,
+        controller::{Controller, Token, TokenPosition},
+        handler::Delegated,
+        lock,
+        mailbox::{Mailbox, Trigger},
+        outcome::EndEvent,
+    },
 };
 use execute_handler::ExecuteHandler;
 use log::{debug, warn};
-use std::{borrow::Cow, fmt::Display};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::Display,
+    ops::Deref,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread::Scope,
+    time::SystemTime,
+};
 
 type Tokens<'a> = Cow<'a, [usize]>;
 
@@ -19,8 +38,96 @@ enum Return<'a> {
     Fork(Tokens<'a>),
     Join(&'a Gateway),
     End(&'a Event),
+    // Event thrown by a subprocess without a matching boundary
+    Throw(&'a Event),
+    // Intermediate catch event or event-based gateway waiting on its events
+    Wait(Waiting<'a>),
+}
+
+// Token waiting for the first trigger to arrive. Continues from the element of the trigger caught.
+#[derive(Debug)]
+struct Waiting<'a> {
+    // Name or id of the element waiting
+    element: &'a str,
+    // Name or id of the element executed before, when a controller tracks the token
+    last: Option<String>,
+    catches: Vec<(Trigger<'a>, &'a Bpmn)>,
+}
+
+// Name or id of the element, None for sequence flows.
+fn element_name(bpmn: &Bpmn) -> Option<&str> {
+    match bpmn {
+        Bpmn::Event(Event { id, name, .. })
+        | Bpmn::Activity(Activity { id, name, .. })
+        | Bpmn::Gateway(Gateway { id, name, .. }) => Some(name.as_deref().unwrap_or(id.bpmn())),
+        Bpmn::SequenceFlow { .. } => None,
+    }
+}
+
+// Keep the element executed before a waiting token, for the status of the controller.
+fn tracked<'a>(
+    result: Result<Return<'a>, RuntimeError>,
+    token: Option<&Token<'_>>,
+) -> Result<Return<'a>, RuntimeError> {
+    match (result, token) {
+        (Ok(Return::Wait(mut wait)), Some(token)) => {
+            wait.last = token.last_executed().map(Into::into);
+            Ok(Return::Wait(wait))
+        }
+        (other, _) => other,
+    }
+}
+
+// Result from executing a process or subprocess
+pub(super) struct Completed<'a, T> {
+    // End events reached in the order visited. A terminating end event is always last.
+    pub(super) end_events: Vec<&'a Event>,
+    // Cancel, error, escalation or signal event not caught in the process
+    thrown: Option<&'a Event>,
+    // Data of the token that ended the process
+    pub(super) data: Option<Data<'a, T>>,
+}
+
+// Process data seen by a token. Tokens share the process data, unless branch-local data is
+// registered and the token was forked.
+pub(super) enum Data<'a, T> {
+    Shared(&'a T),
+    Branch(Arc<T>),
+}
+
+impl<T> Clone for Data<'_, T> {
+    fn clone(&self) -> Self {
+        match self {
+            Data::Shared(data) => Data::Shared(data),
+            Data::Branch(data) => Data::Branch(Arc::clone(data)),
+        }
+    }
+}
+
+impl<T> Data<'_, T> {
+    // Take the data of the token, or a copy if a task interrupted by a boundary still uses it.
+    pub(super) fn into_owned(self, copy: impl Fn(&T) -> T) -> T {
+        match self {
+            Data::Branch(data) => Arc::try_unwrap(data).unwrap_or_else(|data| copy(&data)),
+            Data::Shared(data) => copy(data),
+        }
+    }
+}
+
+impl<T> Deref for Data<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Data::Shared(data) => data,
+            Data::Branch(data) => data,
+        }
+    }
 }
 
+// Token waiting, with the sequence number of the first signal it can catch and its data
+type Waiter<'a, T> = (Waiting<'a>, usize, Data<'a, T>);
+
 macro_rules! maybe_fork {
     ($outputs:expr, $ty:expr) => {
         if $outputs.len() <= 1 {
@@ -36,91 +143,275 @@ macro_rules! maybe_fork {
 impl<T> Process<T> {
     pub(super) fn execute<'a>(
         &'a self,
+        start: usize,
         input: ExecuteInput<'a, T>,
-    ) -> Result<&'a Event, RuntimeError>
+    ) -> Result<Completed<'a, T>, RuntimeError>
     where
         T: Send + Sync,
     {
-        let mut last_visited_end = None;
-        let mut handler = ExecuteHandler::new(input.process.start());
+        let scope = input.mailbox.enter(input.since());
+        let mut waiting = vec![];
+        let result = self.execute_tokens(start, &input, scope, &mut waiting);
+        // Tokens still waiting when the process ends early are withdrawn
+        for (wait, _, _) in waiting {
+            let triggers: Vec<_> = wait.catches.iter().map(|(trigger, _)| *trigger).collect();
+            input.mailbox.withdraw(&triggers);
+        }
+        input.mailbox.leave(scope);
+        result
+    }
+
+    fn execute_tokens<'a>(
+        &'a self,
+        start: usize,
+        input: &ExecuteInput<'a, T>,
+        scope: usize,
+        waiting: &mut Vec<Waiter<'a, T>>,
+    ) -> Result<Completed<'a, T>, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        let mut visited_ends = vec![];
+        let mut handler = ExecuteHandler::new(start, input.data.clone());
         let mut active_tokens = vec![];
+        // Data of the last token that ended
+        let mut ended = None;
+        let positions = input.controller.map(Controller::waiting);
+        // Element caught by a token that continues in the next round, for the status of the controller
+        let mut caught = HashMap::new();
+        let started = input.since();
+        let mut delivered = started;
         loop {
             handler.swap(&mut active_tokens);
             if active_tokens.is_empty() {
-                return last_visited_end.ok_or(DiagramError::MissingEndEvent.into());
+                if waiting.is_empty() {
+                    if visited_ends.is_empty() {
+                        Err(DiagramError::MissingEndEvent)?
+                    }
+                    return Ok(Completed {
+                        end_events: visited_ends,
+                        thrown: None,
+                        data: ended,
+                    });
+                }
+
+                if !input.mailbox.wait(delivered) {
+                    return Err(RuntimeError::Stalled(
+                        waiting
+                            .iter()
+                            .map(|(Waiting { element, .. }, _, _)| element.to_string())
+                            .collect(),
+                    ));
+                }
             }
 
+            if let Some(boundary) = self.interrupted(input, started) {
+                debug!("{boundary} interrupts the subprocess");
+                return Ok(Completed {
+                    end_events: visited_ends,
+                    thrown: Some(boundary),
+                    data: ended,
+                });
+            }
+
+            let tokens: Vec<_> = std::mem::take(&mut active_tokens)
+                .into_iter()
+                .map(|(token, data)| (token, caught.remove(&token), data))
+                .collect();
             let flows_iter = {
                 #[cfg(feature = "parallel")]
                 {
-                    use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-                    let results: Vec<_> = active_tokens
-                        .par_iter()
-                        .map(|token| self.flow_loop(*token, &input))
+                    use rayon::iter::{IntoParallelIterator, ParallelIterator};
+                    // Count all tokens as running before any of them can wait
+                    let tokens: Vec<_> = tokens
+                        .into_iter()
+                        .map(|(token, last, data)| (token, last, data, input.mailbox.running()))
+                        .collect();
+                    let results: Vec<_> = tokens
+                        .into_par_iter()
+                        .map(|(token, last, data, _running)| {
+                            self.flow_loop(token, last, data, input)
+                        })
                         .collect::<Vec<_>>();
                     results.into_iter()
                 }
                 #[cfg(not(feature = "parallel"))]
-                self.flow_alternator(&active_tokens, &input).into_iter()
+                self.flow_alternator(tokens, input).into_iter()
             };
 
-            for flow_result in flows_iter.rev() {
+            for (flow_result, data) in flows_iter.rev() {
                 match flow_result? {
-                    Return::Join(gateway) => handler.consume_token(Some(gateway)),
+                    Return::Join(gateway) => {
+                        handler.consume_token(Some(gateway), data);
+                    }
                     Return::End(event) => {
+                        visited_ends.push(event);
                         match event {
-                            // A subprocess end event, terminate early if a boundary (interrupting) or terminate event
+                            // Ends the process early and is caught by an event subprocess or a boundary (interrupting).
+                            // A regular process without a matching event subprocess just ends the token.
                             Event {
                                 event_type: EventType::End,
                                 symbol:
-                                    Symbol::Cancel
-                                    | Symbol::Error
-                                    | Symbol::Escalation
-                                    | Symbol::Signal
-                                    | Symbol::Terminate,
+                                    Symbol::Cancel | Symbol::Error | Symbol::Escalation | Symbol::Signal,
                                 ..
-                            } if input.is_subprocess => return Ok(event),
+                            } if input.is_subprocess
+                                || self
+                                    .diagram
+                                    .event_subprocess(input.process, event)
+                                    .is_some() =>
+                            {
+                                return self.throw(event, visited_ends, data, input);
+                            }
 
-                            // Regular process
+                            // A subprocess terminate event ends the subprocess and continues with the parent process
                             Event {
                                 event_type: EventType::End,
                                 symbol: Symbol::Terminate,
                                 ..
-                            } => return Ok(event),
+                            } => {
+                                return Ok(Completed {
+                                    end_events: visited_ends,
+                                    thrown: None,
+                                    data: Some(data),
+                                });
+                            }
                             _ => {
-                                last_visited_end.replace(event);
-                                handler.consume_token(None);
+                                if let Some(data) = handler.consume_token(None, data) {
+                                    ended = Some(data);
+                                }
                             }
                         }
                     }
-                    Return::Fork(item) => handler.pending_fork(item),
+                    Return::Throw(event) => return self.throw(event, visited_ends, data, input),
+                    Return::Fork(item) => handler.pending_fork(item, data),
+                    Return::Wait(wait) => waiting.push((wait, input.since(), data)),
                     _ => {}
                 }
 
                 // Check if all inputs have been merged for a gateway, then proceed with its outputs.
-                if let Some(
-                    gateway @ Gateway {
-                        gateway_type,
-                        outputs,
-                        ..
-                    },
-                ) = handler.tokens_consumed()?
-                {
-                    match gateway_type {
-                        GatewayType::Parallel | GatewayType::Inclusive if outputs.len() == 1 => {
-                            handler.immediate(Cow::Borrowed(outputs.ids()));
+                match handler.tokens_consumed()? {
+                    Some((
+                        Some(
+                            gateway @ Gateway {
+                                gateway_type,
+                                outputs,
+                                ..
+                            },
+                        ),
+                        arrived,
+                    )) => {
+                        let data = self.merge_data(
+                            gateway.name.as_deref().unwrap_or(gateway.id.bpmn()),
+                            arrived,
+                            input,
+                        );
+                        match gateway_type {
+                            GatewayType::Parallel | GatewayType::Inclusive
+                                if outputs.len() == 1 =>
+                            {
+                                if let Some(output) = outputs.first() {
+                                    handler.immediate(*output, data);
+                                }
+                            }
+                            GatewayType::Parallel => {
+                                handler.pending_fork(Cow::Borrowed(outputs.ids()), data);
+                            }
+                            GatewayType::Inclusive => {
+                                let tokens =
+                                    self.handle_inclusive_gateway(input, gateway, &data)?;
+                                handler.pending_fork(tokens, data);
+                            }
+                            _ => {}
                         }
-                        GatewayType::Parallel => {
-                            handler.pending_fork(Cow::Borrowed(outputs.ids()));
+                    }
+                    // Forked tokens that ended without a join
+                    Some((None, arrived)) if !arrived.is_empty() => {
+                        let element = visited_ends
+                            .last()
+                            .map_or("", |event| event.name.as_deref().unwrap_or(event.id.bpmn()));
+                        if let Some(data) = handler.ended(self.merge_data(element, arrived, input))
+                        {
+                            ended = Some(data);
                         }
-                        GatewayType::Inclusive => {
-                            handler.pending_fork(self.handle_inclusive_gateway(&input, gateway)?);
+                    }
+                    _ => {}
+                }
+            }
+
+            // Continue with the tokens that caught a trigger. Other branches of an event-based gateway are withdrawn.
+            delivered = input.mailbox.sequence();
+            let mut arrived = vec![];
+            for (wait, since, data) in std::mem::take(waiting) {
+                let triggers: Vec<_> = wait.catches.iter().map(|(trigger, _)| *trigger).collect();
+                match input.mailbox.catch(since, &triggers) {
+                    Some((position, payload)) => arrived.push((wait, position, payload, data)),
+                    None => waiting.push((wait, since, data)),
+                }
+            }
+            if let Some(positions) = &positions {
+                positions.update(
+                    waiting
+                        .iter()
+                        .map(|(wait, _, _)| {
+                            TokenPosition::waiting(wait.element, wait.last.as_deref())
+                        })
+                        .collect(),
+                );
+            }
+
+            for (wait, position, payload, data) in arrived {
+                let (trigger, bpmn) = &wait.catches[position];
+                debug!("{} caught {trigger:?}", wait.element);
+                let element = element_name(bpmn);
+
+                // Step onto the caught element before running its function, blocking while paused.
+                // An event-based gateway continues from the gateway.
+                let last = if element == Some(wait.element) {
+                    wait.last.as_deref()
+                } else {
+                    Some(wait.element)
+                };
+                let mut tracker = input.controller.map(|controller| controller.token(last));
+                if let (Some(tracker), Some(element)) = (&mut tracker, element) {
+                    tracker.step(element);
+                }
+                let task = payload.map(|payload| payload(&data));
+
+                // Receive tasks run once the message has arrived, while external tasks continue
+                // with the result of the job.
+                let next = match (bpmn, trigger) {
+                    (Bpmn::Activity(activity), Trigger::Work(_)) => {
+                        self.follow(activity, task.unwrap_or_default(), input)?
+                    }
+                    (Bpmn::Event(event), _) => self.caught(event, &data)?,
+                    (Bpmn::Activity(activity), _) => self.task(activity, &data, input)?,
+                    _ => continue,
+                };
+                match next {
+                    Return::Continue(token) => {
+                        if let Some(element) = element.filter(|_| input.controller.is_some()) {
+                            caught.insert(token, element);
                         }
-                        _ => {}
+                        handler.immediate(token, data)
                     }
+                    Return::Fork(item) => handler.pending_fork(item, data),
+                    _ => {}
                 }
             }
-            handler.commit();
+            handler.commit(|data| self.fork_data(data));
+            input.round.store(delivered, Ordering::Relaxed);
+
+            // Older signals can't be caught by waiting tokens, the next round or a signal boundary
+            // interrupting the subprocess
+            let interruptible = input.parent.is_some_and(|(process, Activity { id, .. })| {
+                process.events.has_signal_boundary(id)
+            });
+            let lowest = waiting
+                .iter()
+                .map(|(_, since, _)| *since)
+                .chain(interruptible.then_some(started))
+                .fold(delivered, usize::min);
+            input.mailbox.advance(scope, lowest);
         }
     }
 
@@ -128,15 +419,19 @@ impl<T> Process<T> {
     fn flow_loop<'a>(
         &'a self,
         mut current_id: usize,
+        last: Option<&str>,
+        mut data: Data<'a, T>,
         input: &ExecuteInput<'a, T>,
-    ) -> Result<Return<'a>, RuntimeError>
+    ) -> (Result<Return<'a>, RuntimeError>, Data<'a, T>)
     where
         T: Send + Sync,
     {
+        let mut token = input.controller.map(|controller| controller.token(last));
         loop {
-            match self.flow(current_id, input) {
+            input.step(&mut token, current_id);
+            match self.flow(current_id, &mut data, input) {
                 Ok(Return::Continue(value)) => current_id = value,
-                other => return other,
+                other => return (tracked(other, token.as_ref()), data),
             }
         }
     }
@@ -145,17 +440,25 @@ impl<T> Process<T> {
     #[cfg(not(feature = "parallel"))]
     fn flow_alternator<'a: 'b, 'b>(
         &'a self,
-        tokens: &[usize],
+        tokens: Vec<(usize, Option<&str>, Data<'a, T>)>,
         input: &ExecuteInput<'a, T>,
-    ) -> Vec<Result<Return<'a>, RuntimeError>>
+    ) -> Vec<(Result<Return<'a>, RuntimeError>, Data<'a, T>)>
     where
         T: Send + Sync,
     {
         let mut result = vec![];
-        let mut queue = std::collections::VecDeque::from_iter(tokens.iter().cloned());
-        while let Some(token) = queue.pop_front() {
-            match self.flow(token, input) {
-                Ok(Return::Continue(value)) => queue.push_back(value),
+        let mut queue =
+            std::collections::VecDeque::from_iter(tokens.into_iter().map(|(token, last, data)| {
+                (
+                    token,
+                    input.controller.map(|controller| controller.token(last)),
+                    data,
+                )
+            }));
+        while let Some((token, mut tracker, mut data)) = queue.pop_front() {
+            input.step(&mut tracker, token);
+            match self.flow(token, &mut data, input) {
+                Ok(Return::Continue(value)) => queue.push_back((value, tracker, data)),
 
                 // terminate event, stop working.
                 end @ Ok(Return::End(Event {
@@ -163,10 +466,10 @@ impl<T> Process<T> {
                     symbol: Symbol::Terminate,
                     ..
                 })) => {
-                    result.push(end);
+                    result.push((end, data));
                     break;
                 }
-                other => result.push(other),
+                other => result.push((tracked(other, tracker.as_ref()), data)),
             }
         }
         result
@@ -176,17 +479,19 @@ impl<T> Process<T> {
     fn flow<'a>(
         &'a self,
         mut current_id: usize,
+        data: &mut Data<'a, T>,
         input: &ExecuteInput<'a, T>,
     ) -> Result<Return<'a>, RuntimeError>
     where
         T: Send + Sync,
     {
-        current_id = *match input.process.get(current_id).ok_or_else(|| {
+        let bpmn = input.process.get(current_id).ok_or_else(|| {
             RuntimeError::Engine(format!(
                 "could not fetch bpmn data with index {}",
                 current_id
             ))
-        })? {
+        })?;
+        current_id = *match bpmn {
             Bpmn::Event(
                 event @ Event {
                     event_type,
@@ -202,22 +507,31 @@ impl<T> Process<T> {
                     EventType::Start | EventType::Boundary => {
                         maybe_fork!(outputs, event)
                     }
-                    EventType::IntermediateCatch => {
-                        if !matches!(symbol, Symbol::Link)
-                            && let Some(index) = self.intermediate_catch_callback
+                    EventType::IntermediateCatch => match (symbol, self.trigger(bpmn, input)?) {
+                        // Messages are only delivered to instances owned by a runtime. Signals are waited on if
+                        // the process throws them or a runtime can deliver them, otherwise the event is passed.
+                        // An unnamed signal catch event has nothing to wait on and is passed as well.
+                        (_, Some(trigger))
+                            if match symbol {
+                                Symbol::Message => input.mailbox.is_external(),
+                                Symbol::Signal => {
+                                    input.mailbox.is_external()
+                                        || name
+                                            .as_deref()
+                                            .is_some_and(|name| self.diagram.throws_signal(name))
+                                }
+                                _ => true,
+                            } =>
                         {
-                            self.handler
-                                .run_end_or_intermediate(
-                                    index,
-                                    input.data,
-                                    name.as_deref(),
-                                    *symbol,
-                                )?
-                                .map_err(RuntimeError::Panic)?;
+                            debug!("{event} waiting on {trigger:?}");
+                            return Ok(Return::Wait(Waiting {
+                                element: name.as_deref().unwrap_or(id.bpmn()),
+                                last: None,
+                                catches: vec![(trigger, bpmn)],
+                            }));
                         }
-
-                        maybe_fork!(outputs, event)
-                    }
+                        _ => return self.caught(event, data),
+                    },
                     EventType::IntermediateThrow => match (name.as_ref(), symbol) {
                         (Some(name), Symbol::Link) => {
                             input.process.events.catch_event_link(name)?
@@ -228,14 +542,13 @@ impl<T> Process<T> {
                         _ => {
                             if let Some(index) = self.intermediate_throw_callback {
                                 self.handler
-                                    .run_end_or_intermediate(
-                                        index,
-                                        input.data,
-                                        name.as_deref(),
-                                        *symbol,
-                                    )?
+                                    .run_end_or_intermediate(index, event, data)?
                                     .map_err(RuntimeError::Panic)?;
                             }
+
+                            if let (Symbol::Signal, Some(name)) = (symbol, name) {
+                                input.mailbox.signal(name);
+                            }
                             maybe_fork!(outputs, event)
                         }
                     },
@@ -244,15 +557,13 @@ impl<T> Process<T> {
                             && let Some(index) = self.end_callback
                         {
                             self.handler
-                                .run_end_or_intermediate(
-                                    index,
-                                    input.data,
-                                    name.as_deref(),
-                                    *symbol,
-                                )?
+                                .run_end_or_intermediate(index, event, data)?
                                 .map_err(RuntimeError::Panic)?;
                         }
 
+                        if let (Symbol::Signal, Some(name)) = (symbol, name) {
+                            input.mailbox.signal(name);
+                        }
                         return Ok(Return::End(event));
                     }
                 }
@@ -261,6 +572,7 @@ impl<T> Process<T> {
                 activity @ Activity {
                     activity_type,
                     id,
+                    name,
                     func_idx,
                     data_index,
                     outputs,
@@ -278,24 +590,41 @@ impl<T> Process<T> {
                     | ActivityType::SendTask
                     | ActivityType::ManualTask
                     | ActivityType::BusinessRuleTask => {
-                        match func_idx
-                            .map(|index| self.handler.run_task(index, input.data))
-                            .ok_or_else(|| {
-                                RuntimeError::Engine(format!("missing function {:?}", activity))
-                            })?? {
-                            Task::Boundary(name, symbol) => input
-                                .process
-                                .events
-                                .boundary(id, symbol, name.as_deref())
-                                .ok_or_else(|| {
-                                    DiagramError::MissingBoundary(
-                                        format!("({name:?},{symbol})"),
-                                        activity.to_string(),
-                                    )
-                                })?,
-                            Task::Default => maybe_fork!(outputs, activity),
-                            Task::Panic(e) => Err(RuntimeError::Panic(e))?,
+                        // A receive task in an instance owned by a runtime waits for its message
+                        if matches!(activity_type, ActivityType::ReceiveTask)
+                            && input.mailbox.is_external()
+                            && let Some(trigger) = self.trigger(bpmn, input)?
+                        {
+                            debug!("{activity} waiting on {trigger:?}");
+                            return Ok(Return::Wait(Waiting {
+                                element: name.as_deref().unwrap_or(id.bpmn()),
+                                last: None,
+                                catches: vec![(trigger, bpmn)],
+                            }));
+                        }
+
+                        // An external task publishes a job and a human task an inbox item. The token waits
+                        // until a worker or a person has completed it.
+                        if let Some(index) = func_idx
+                            && let Some(delegated) = self.handler.delegated(*index, data)
+                        {
+                            let element = name.as_deref().unwrap_or(id.bpmn());
+                            let (instance, work) = input.mailbox.work().ok_or_else(|| {
+                                DiagramError::NotSupported(format!("{activity} outside an engine"))
+                            })?;
+                            let published = match delegated {
+                                Delegated::Job(data) => {
+                                    work.publish_job(instance, element, id.bpmn(), data)
+                                }
+                                Delegated::UserTask => work.publish_user_task(instance, activity),
+                            };
+                            return Ok(Return::Wait(Waiting {
+                                element,
+                                last: None,
+                                catches: vec![(Trigger::Work(published), bpmn)],
+                            }));
                         }
+                        return self.task(activity, data, input);
                     }
                     ActivityType::SubProcess => {
                         let subprocess = match data_index {
@@ -306,29 +635,56 @@ impl<T> Process<T> {
                             )))?,
                         };
 
-                        if let Event {
-                            event_type: EventType::End,
-                            symbol:
-                                symbol @ (Symbol::Cancel
-                                | Symbol::Error
-                                | Symbol::Escalation
-                                | Symbol::Signal),
-
-                            name,
-                            ..
-                        } = self.execute(ExecuteInput::new(subprocess, true, input.data))?
+                        let scoped = self.enter_scope(activity, data);
+                        let completed = {
+                            #[cfg(feature = "parallel")]
+                            let _suspended = input.mailbox.suspend();
+                            // Entered at an element when the process runs from inside the subprocess
+                            let (start, entry) = match input.take_entry().split_first() {
+                                Some((start, entry)) => (*start, entry),
+                                None => (subprocess.start()?, &[][..]),
+                            };
+                            self.execute(
+                                start,
+                                input.subprocess(
+                                    subprocess,
+                                    Some((input.process, activity)),
+                                    entry,
+                                    scoped.clone().unwrap_or_else(|| data.clone()),
+                                ),
+                            )?
+                        };
+                        // Continue with the data the subprocess ended with. A subprocess with a scope of
+                        // its own only passes on its mapped outputs.
+                        match &scoped {
+                            Some(scoped) => self.leave_scope(
+                                activity,
+                                data,
+                                completed.data.as_deref().unwrap_or(scoped),
+                            ),
+                            None => {
+                                if let Some(ended) = completed.data {
+                                    *data = ended;
+                                }
+                            }
+                        }
+
+                        if let Some(
+                            thrown @ Event {
+                                symbol, name, code, ..
+                            },
+                        ) = completed.thrown
                         {
-                            // Jump to boundary
-                            input
-                                .process
-                                .events
-                                .boundary(id, *symbol, name.as_deref())
-                                .ok_or_else(|| {
-                                    DiagramError::MissingBoundary(
-                                        symbol.to_string(),
-                                        activity.to_string(),
-                                    )
-                                })?
+                            // Jump to boundary or propagate to the enclosing process
+                            match input.process.events.boundary(
+                                id,
+                                *symbol,
+                                name.as_deref(),
+                                code.as_deref(),
+                            ) {
+                                Some(boundary) => boundary,
+                                None => return Ok(Return::Throw(thrown)),
+                            }
                         } else {
                             // Continue from subprocess
                             maybe_fork!(outputs, activity)
@@ -356,7 +712,7 @@ impl<T> Process<T> {
                     GatewayType::Exclusive if outputs.len() == 1 => outputs.first().unwrap(),
                     GatewayType::Exclusive => {
                         match func_idx
-                            .map(|index| self.handler.run_exclusive(index, input.data))
+                            .map(|index| self.handler.run_exclusive(index, gateway, data))
                             .ok_or_else(|| {
                                 RuntimeError::Engine(format!("missing function {:?}", gateway))
                             })?? {
@@ -373,11 +729,34 @@ impl<T> Process<T> {
                         return Ok(Return::Fork(Cow::Borrowed(outputs.ids())));
                     }
                     GatewayType::Inclusive => {
-                        return Ok(Return::Fork(self.handle_inclusive_gateway(input, gateway)?));
+                        return Ok(Return::Fork(
+                            self.handle_inclusive_gateway(input, gateway, data)?,
+                        ));
+                    }
+                    // Without a function, wait for the first event to arrive
+                    GatewayType::EventBased if func_idx.is_none() => {
+                        let catches = input
+                            .process
+                            .targets(outputs)
+                            .map(|target| {
+                                let trigger = self.trigger(target, input)?.ok_or_else(|| {
+                                    DiagramError::NotSupported(format!(
+                                        "{gateway} can only wait on named message, signal and timer events, and named receive tasks"
+                                    ))
+                                })?;
+                                Ok((trigger, target))
+                            })
+                            .collect::<Result<Vec<_>, RuntimeError>>()?;
+                        debug!("{gateway} waiting on {catches:?}");
+                        return Ok(Return::Wait(Waiting {
+                            element: gateway.name.as_deref().unwrap_or(gateway.id.bpmn()),
+                            last: None,
+                            catches,
+                        }));
                     }
                     GatewayType::EventBased => {
                         match func_idx
-                            .map(|index| self.handler.run_eventbased(index, input.data))
+                            .map(|index| self.handler.run_eventbased(index, gateway, data))
                             .ok_or_else(|| {
                                 RuntimeError::Engine(format!("missing function {:?}", gateway))
                             })?? {
@@ -408,15 +787,282 @@ impl<T> Process<T> {
         Ok(Return::Continue(current_id))
     }
 
+    // Run the intermediate catch callback once the event has been caught and continue with its outputs.
+    fn caught<'a>(
+        &'a self,
+        event @ Event {
+            symbol, outputs, ..
+        }: &'a Event,
+        data: &T,
+    ) -> Result<Return<'a>, RuntimeError> {
+        if !matches!(symbol, Symbol::Link)
+            && let Some(index) = self.intermediate_catch_callback
+        {
+            self.handler
+                .run_end_or_intermediate(index, event, data)?
+                .map_err(RuntimeError::Panic)?;
+        }
+        Ok(Return::Continue(*maybe_fork!(outputs, event)))
+    }
+
+    // Run the task function and continue with the returned flow or boundary.
+    fn task<'a>(
+        &'a self,
+        activity: &'a Activity,
+        data: &Data<'a, T>,
+        input: &ExecuteInput<'a, T>,
+    ) -> Result<Return<'a>, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        let scoped = self.enter_scope(activity, data);
+        let task = activity
+            .func_idx
+            .map(|index| self.run_task(index, activity, scoped.as_ref().unwrap_or(data), input))
+            .ok_or_else(|| RuntimeError::Engine(format!("missing function {:?}", activity)))??;
+        if let Some(scoped) = &scoped {
+            self.leave_scope(activity, data, scoped);
+        }
+        self.follow(activity, task, input)
+    }
+
+    // Continue with the flow or boundary selected by the task result.
+    fn follow<'a>(
+        &self,
+        activity @ Activity { id, outputs, .. }: &'a Activity,
+        task: Task,
+        input: &ExecuteInput<'a, T>,
+    ) -> Result<Return<'a>, RuntimeError> {
+        let next = match task {
+            Task::Boundary(name, symbol) => input
+                .process
+                .events
+                .boundary(id, symbol, name.as_deref(), None)
+                .ok_or_else(|| {
+                    DiagramError::MissingBoundary(
+                        format!("({name:?},{symbol})"),
+                        activity.to_string(),
+                    )
+                })?,
+            Task::Code(code, symbol) => input
+                .process
+                .events
+                .boundary(id, symbol, None, Some(&code))
+                .ok_or_else(|| {
+                    DiagramError::MissingBoundary(
+                        format!("({symbol} code {code})"),
+                        activity.to_string(),
+                    )
+                })?,
+            Task::Default => maybe_fork!(outputs, activity),
+            Task::Panic(e) => Err(RuntimeError::Panic(e))?,
+        };
+        Ok(Return::Continue(*next))
+    }
+
+    // The trigger a waiting token catches to continue from the element. Timers are armed.
+    fn trigger<'a>(
+        &self,
+        bpmn: &'a Bpmn,
+        input: &ExecuteInput<'a, T>,
+    ) -> Result<Option<Trigger<'a>>, RuntimeError> {
+        Ok(match bpmn {
+            Bpmn::Event(Event {
+                event_type: EventType::IntermediateCatch,
+                symbol,
+                name,
+                timer,
+                ..
+            }) => match (symbol, name, timer) {
+                (Symbol::Signal, Some(name), _) => Some(Trigger::Signal(name)),
+                (Symbol::Message, Some(name), _) => Some(Trigger::Message(name)),
+                (Symbol::Timer, _, Some(timer)) => {
+                    // A cycle without repetitions left continues like a date that has passed
+                    let deadline = timer
+                        .deadline(self.clock.now())
+                        .unwrap_or(SystemTime::UNIX_EPOCH);
+                    Some(Trigger::Timer(input.mailbox.arm(&self.clock, deadline)))
+                }
+                _ => None,
+            },
+            Bpmn::Activity(Activity {
+                activity_type: ActivityType::ReceiveTask,
+                name: Some(name),
+                ..
+            }) => Some(Trigger::Message(name)),
+            _ => None,
+        })
+    }
+
+    // A signal boundary on the enclosing activity that caught a signal thrown since `since`.
+    fn interrupted<'a>(&self, input: &ExecuteInput<'a, T>, since: usize) -> Option<&'a Event> {
+        let (process, Activity { id, .. }) = input.parent?;
+        if !process.events.has_signal_boundary(id) {
+            return None;
+        }
+        input.mailbox.signals(since).iter().find_map(|name| {
+            match process.get(
+                *process
+                    .events
+                    .boundary(id, Symbol::Signal, Some(name), None)?,
+            ) {
+                Some(Bpmn::Event(event)) => Some(event),
+                _ => None,
+            }
+        })
+    }
+
+    // A timer boundary with a timer definition or a signal boundary runs the task on a scoped thread.
+    // If the task has not completed in time or a signal is caught, the boundary is used and the result
+    // from the task is discarded once it completes.
+    fn run_task<'a>(
+        &'a self,
+        index: usize,
+        activity @ Activity { id, .. }: &'a Activity,
+        data: &Data<'a, T>,
+        input: &ExecuteInput<'a, T>,
+    ) -> Result<Task, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        let timeout = input.process.events.timeout(id, self.clock.now());
+        let signals = input.process.events.has_signal_boundary(id);
+        if timeout.is_none() && !signals {
+            return self.handler.run_task(index, activity, data);
+        }
+
+        enum Wake {
+            Task(Result<Task, RuntimeError>),
+            Timer,
+            Signal(String),
+        }
+
+        let catches = |name: &str| {
+            input
+                .process
+                .events
+                .boundary(id, Symbol::Signal, Some(name), None)
+                .is_some()
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let listener = signals.then(|| {
+            let sender = sender.clone();
+            input.mailbox.listen(Box::new(move |name| {
+                let _ = sender.send(Wake::Signal(name.to_string()));
+            }))
+        });
+
+        // A signal thrown by a concurrent token before the task started
+        let caught = signals
+            .then(|| input.mailbox.signals(input.since()))
+            .and_then(|thrown| thrown.into_iter().find(|name| catches(name)));
+
+        let result = if let Some(name) = caught {
+            debug!("signal `{name}` interrupts task with index {index}");
+            Ok(Task::Boundary(Some(Cow::Owned(name)), Symbol::Signal))
+        } else {
+            let timer_sender = sender.clone();
+            let (handler, data) = (&self.handler, data.clone());
+            input.spawner.spawn(Box::new(move || {
+                // Receiver is gone if the task was interrupted
+                let _ = sender.send(Wake::Task(handler.run_task(index, activity, &data)));
+            }));
+            let wake = timeout.map(|(_, deadline)| {
+                self.clock.wake_at(
+                    deadline,
+                    Box::new(move || {
+                        let _ = timer_sender.send(Wake::Timer);
+                    }),
+                )
+            });
+
+            let result = loop {
+                match receiver.recv() {
+                    Ok(Wake::Task(result)) => break result,
+                    Ok(Wake::Timer) => {
+                        let name =
+                            match timeout.and_then(|(boundary, _)| input.process.get(boundary)) {
+                                Some(Bpmn::Event(event)) => {
+                                    debug!("{event} reached its deadline");
+                                    event.name.clone()
+                                }
+                                _ => None,
+                            };
+                        break Ok(Task::Boundary(name.map(Cow::Owned), Symbol::Timer));
+                    }
+                    Ok(Wake::Signal(name)) if catches(&name) => {
+                        debug!("signal `{name}` interrupts task with index {index}");
+                        break Ok(Task::Boundary(Some(Cow::Owned(name)), Symbol::Signal));
+                    }
+                    Ok(Wake::Signal(_)) => {}
+                    Err(_) => {
+                        break Err(RuntimeError::Engine(format!(
+                            "task with index {index} stopped without a result"
+                        )));
+                    }
+                }
+            };
+
+            // The task completed or was interrupted, so the timer is no longer needed
+            if let Some(wake) = wake {
+                self.clock.cancel(wake);
+            }
+            result
+        };
+
+        if let Some(listener) = listener {
+            input.mailbox.unlisten(listener);
+        }
+        result
+    }
+
+    // Run a matching event subprocess for the thrown event. Otherwise, a subprocess returns the event
+    // to be caught by the enclosing process and the main process fails with an uncaught event.
+    fn throw<'a>(
+        &'a self,
+        thrown: &'a Event,
+        end_events: Vec<&'a Event>,
+        data: Data<'a, T>,
+        input: &ExecuteInput<'a, T>,
+    ) -> Result<Completed<'a, T>, RuntimeError>
+    where
+        T: Send + Sync,
+    {
+        let (thrown, data) = match self.diagram.event_subprocess(input.process, thrown) {
+            Some((subprocess, start)) => {
+                debug!("{thrown} caught by event subprocess");
+                let completed = self.execute(
+                    start,
+                    input.subprocess(subprocess, input.parent, &[], data.clone()),
+                )?;
+                (completed.thrown, completed.data.unwrap_or(data))
+            }
+            None => (Some(thrown), data),
+        };
+
+        match thrown {
+            Some(thrown) if !input.is_subprocess => {
+                Err(RuntimeError::Uncaught(EndEvent::from(thrown)))
+            }
+            thrown => Ok(Completed {
+                end_events,
+                thrown,
+                data: Some(data),
+            }),
+        }
+    }
+
     fn handle_inclusive_gateway<'a>(
         &'a self,
         input: &ExecuteInput<'a, T>,
         gateway @ Gateway {
             func_idx, outputs, ..
         }: &'a Gateway,
+        data: &T,
     ) -> Result<Tokens<'a>, RuntimeError> {
         let value = match func_idx
-            .map(|index| self.handler.run_inclusive(index, input.data))
+            .map(|index| self.handler.run_inclusive(index, gateway, data))
             .ok_or_else(|| RuntimeError::Engine(format!("missing function {:?}", gateway)))??
         {
             Inclusive::Flow(value) => input.find_flow(&value, outputs, gateway)?,
@@ -444,24 +1090,143 @@ impl<T> Process<T> {
         };
         Ok(Cow::Owned(vec![*value]))
     }
+
+    // Data of an activity with a scope of its own, when scopes are registered. A subprocess always
+    // has its own scope and a task only if it has a mapping.
+    fn enter_scope<'a>(&self, activity: &Activity, data: &T) -> Option<Data<'a, T>> {
+        let scopes = self.handler.scopes()?;
+        if activity.mapping.is_none() && activity.activity_type != ActivityType::SubProcess {
+            return None;
+        }
+        Some(Data::Branch(Arc::new((scopes.enter)(
+            data,
+            activity.mapping.as_ref(),
+        ))))
+    }
+
+    // Copy the mapped outputs of the activity to the enclosing data.
+    fn leave_scope(&self, activity: &Activity, data: &T, scoped: &T) {
+        if let Some((scopes, mapping)) = self.handler.scopes().zip(activity.mapping.as_ref()) {
+            (scopes.leave)(data, scoped, mapping);
+        }
+    }
+
+    // Data for a forked token, a copy when branch-local data is registered.
+    fn fork_data<'a>(&self, data: &Data<'a, T>) -> Data<'a, T> {
+        match self.handler.branches() {
+            Some(branches) => Data::Branch(Arc::new((branches.fork)(data))),
+            None => data.clone(),
+        }
+    }
+
+    // Data for the token continuing after the join. Branch-local data is merged.
+    fn merge_data<'a>(
+        &self,
+        element: &str,
+        arrived: Vec<Data<'a, T>>,
+        input: &ExecuteInput<'a, T>,
+    ) -> Data<'a, T> {
+        let Some(branches) = self.handler.branches() else {
+            return input.data.clone();
+        };
+        debug!("merge {} branches at {element}", arrived.len());
+        let arrived = arrived
+            .into_iter()
+            .map(|data| data.into_owned(&branches.fork))
+            .collect();
+        Data::Branch(Arc::new((branches.merge)(element, arrived)))
+    }
+}
+
+// Spawn work on a scoped thread that is allowed to outlive the token that started it.
+pub(super) trait Spawn<'a>: Sync {
+    fn spawn(&'a self, func: Box<dyn FnOnce() + Send + 'a>);
+}
+
+impl<'scope> Spawn<'scope> for Scope<'scope, '_> {
+    fn spawn(&'scope self, func: Box<dyn FnOnce() + Send + 'scope>) {
+        Scope::spawn(self, func);
+    }
 }
 
 // Data for the execution engine.
 pub(super) struct ExecuteInput<'a, T> {
     process: &'a ProcessData,
     is_subprocess: bool,
-    data: &'a T,
+    // Enclosing process and subprocess activity, whose boundaries can interrupt the subprocess
+    parent: Option<(&'a ProcessData, &'a Activity)>,
+    // Data of the token starting the process or subprocess
+    data: Data<'a, T>,
+    spawner: &'a dyn Spawn<'a>,
+    mailbox: &'a Mailbox<T>,
+    controller: Option<&'a Controller>,
+    // Elements to enter the next subprocesses at, one for each nesting level. Taken by the first subprocess.
+    entry: Mutex<&'a [usize]>,
+    // Sequence number of the first signal that tokens in the current round can catch.
+    // Signals thrown by concurrent tokens in the same round are caught, even if thrown before a token arrives.
+    round: AtomicUsize,
 }
 
 impl<'a, T> ExecuteInput<'a, T> {
-    pub(super) fn new(process: &'a ProcessData, is_subprocess: bool, data: &'a T) -> Self {
+    pub(super) fn new(
+        process: &'a ProcessData,
+        data: &'a T,
+        spawner: &'a dyn Spawn<'a>,
+        mailbox: &'a Mailbox<T>,
+        controller: Option<&'a Controller>,
+        entry: &'a [usize],
+    ) -> Self {
+        Self {
+            process,
+            is_subprocess: false,
+            parent: None,
+            data: Data::Shared(data),
+            spawner,
+            mailbox,
+            controller,
+            entry: Mutex::new(entry),
+            round: AtomicUsize::new(mailbox.sequence()),
+        }
+    }
+
+    fn subprocess(
+        &self,
+        process: &'a ProcessData,
+        parent: Option<(&'a ProcessData, &'a Activity)>,
+        entry: &'a [usize],
+        data: Data<'a, T>,
+    ) -> Self {
         Self {
             process,
-            is_subprocess,
+            is_subprocess: true,
+            parent,
             data,
+            spawner: self.spawner,
+            mailbox: self.mailbox,
+            controller: self.controller,
+            entry: Mutex::new(entry),
+            round: AtomicUsize::new(self.since()),
         }
     }
 
+    // Move the token to the element, blocking while the controller is paused. Sequence flows are not steps.
+    fn step(&self, token: &mut Option<Token<'a>>, current_id: usize) {
+        let Some(token) = token else {
+            return;
+        };
+        if let Some(element) = self.process.get(current_id).and_then(element_name) {
+            token.step(element);
+        }
+    }
+
+    fn take_entry(&self) -> &'a [usize] {
+        std::mem::take(&mut *lock(&self.entry))
+    }
+
+    fn since(&self) -> usize {
+        self.round.load(Ordering::Relaxed)
+    }
+
     fn find_flow(
         &self,
         search: &str,
@@ -5,23 +5,28 @@ use crate::{
 use log::trace;
 use std::fmt::Display;
 
-#[derive(Default, Debug)]
-pub(super) struct ExecuteHandler<'a> {
-    ready: Vec<usize>,
-    pending: Vec<Tokens<'a>>,
-    stack: Vec<TokenData<'a>>,
+// Gateway joined, if any, and the data of the consumed tokens.
+type Consumed<'a, D> = (Option<&'a Gateway>, Vec<D>);
+
+// Tokens with the data they carry. `D` is the process data seen by a token.
+#[derive(Debug)]
+pub(super) struct ExecuteHandler<'a, D> {
+    ready: Vec<(usize, D)>,
+    pending: Vec<(Tokens<'a>, D)>,
+    stack: Vec<TokenData<'a, D>>,
 }
 
-impl<'a> ExecuteHandler<'a> {
-    pub(super) fn new(start_token: usize) -> Self {
+impl<'a, D> ExecuteHandler<'a, D> {
+    pub(super) fn new(start_token: usize, data: D) -> Self {
         Self {
-            ready: vec![start_token],
-            ..Default::default()
+            ready: vec![(start_token, data)],
+            pending: Default::default(),
+            stack: Default::default(),
         }
     }
 
     // Swap tokens with consumer
-    pub(super) fn swap(&mut self, consumer: &mut Vec<usize>) {
+    pub(super) fn swap(&mut self, consumer: &mut Vec<(usize, D)>) {
         std::mem::swap(&mut self.ready, consumer);
 
         // Clear if something was left from consumer
@@ -30,33 +35,50 @@ impl<'a> ExecuteHandler<'a> {
 
     // Push directly to ready without the involvement of token_stack.
     // When we JOIN a gateway with one output we should not increase the token_stack.
-    pub(super) fn immediate(&mut self, item: Tokens<'a>) {
-        self.ready.extend(item.iter());
+    pub(super) fn immediate(&mut self, token: usize, data: D) {
+        self.ready.push((token, data));
     }
 
     // If a gateway FORK is involved, we need to use the token stack. Even if the gateway only selects one flow.
-    pub(super) fn pending_fork(&mut self, item: Tokens<'a>) {
-        self.pending.push(item);
+    pub(super) fn pending_fork(&mut self, item: Tokens<'a>, data: D) {
+        self.pending.push((item, data));
     }
 
-    // Commit all new tokens.
-    pub(super) fn commit(&mut self) {
-        for item in self.pending.drain(..) {
+    // Commit all new tokens. Each new token gets the data returned by `fork`.
+    pub(super) fn commit(&mut self, fork: impl Fn(&D) -> D) {
+        for (item, data) in self.pending.drain(..) {
             trace!("NEW TOKENS {}", item.len());
             self.stack.push(TokenData::new(item.len()));
-            self.ready.extend(item.iter());
+            self.ready
+                .extend(item.iter().map(|token| (*token, fork(&data))));
         }
     }
 
     // Consume a token. Might be a gateway join or end event.
-    pub(super) fn consume_token(&mut self, join: Option<&'a Gateway>) {
-        if let Some(token_data) = self.stack.last_mut() {
-            token_data.consume(join);
+    // Returns the data if the token was not forked.
+    pub(super) fn consume_token(&mut self, join: Option<&'a Gateway>, data: D) -> Option<D> {
+        match self.stack.last_mut() {
+            Some(token_data) => {
+                token_data.consume(join, data);
+                None
+            }
+            None => Some(data),
+        }
+    }
+
+    // Keep the data of forked tokens that ended without a join. Returns the data if they were not forked.
+    pub(super) fn ended(&mut self, data: D) -> Option<D> {
+        match self.stack.last_mut() {
+            Some(token_data) => {
+                token_data.arrived.push(data);
+                None
+            }
+            None => Some(data),
         }
     }
 
-    // Once all tokens have been consumed, return the gateway involved.
-    pub(super) fn tokens_consumed(&mut self) -> Result<Option<&'a Gateway>, RuntimeError> {
+    // Once all tokens have been consumed, return the gateway involved and the data of the tokens.
+    pub(super) fn tokens_consumed(&mut self) -> Result<Option<Consumed<'a, D>>, RuntimeError> {
         if let Some(mut token_data) = self.stack.pop_if(|token_data| token_data.consumed())
             && let tokens_arrived = token_data.joined.len()
         {
@@ -82,31 +104,36 @@ impl<'a> ExecuteHandler<'a> {
                     "Execution stopped. Not enough tokens at {gateway}"
                 )))?
             }
-            return Ok(gateway);
+            return Ok(Some((gateway, token_data.arrived)));
         }
         Ok(None)
     }
 }
 
-#[derive(Default, Debug)]
-struct TokenData<'a> {
+#[derive(Debug)]
+struct TokenData<'a, D> {
     created: usize,
     joined: Vec<&'a Gateway>,
     consumed: usize,
+    // Data of the consumed tokens
+    arrived: Vec<D>,
 }
 
-impl<'a> TokenData<'a> {
+impl<'a, D> TokenData<'a, D> {
     fn new(created: usize) -> Self {
         Self {
             created,
-            ..Default::default()
+            joined: Default::default(),
+            consumed: 0,
+            arrived: Default::default(),
         }
     }
 
-    fn consume(&mut self, maybe_gateway: Option<&'a Gateway>) {
+    fn consume(&mut self, maybe_gateway: Option<&'a Gateway>, data: D) {
         if let Some(gateway) = maybe_gateway {
             self.joined.push(gateway)
         }
+        self.arrived.push(data);
         self.consumed += 1;
         trace!("TOKENS CONSUMED {}", self.consumed);
     }
@@ -116,7 +143,7 @@ impl<'a> TokenData<'a> {
     }
 }
 
-impl<'a> Display for TokenData<'a> {
+impl<'a, D> Display for TokenData<'a, D> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
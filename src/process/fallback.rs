@@ -0,0 +1,60 @@
+use super::{ProcessBuilder, handler::Callback};
+use crate::api::{Element, Task};
+
+/// Functions used for all tasks that match, instead of one function per task. They are called with the
+/// task, so that one function can dispatch on its name or id. A task uses a matching function only if no
+/// function is registered with its name or id. Matching functions are tried in the order they are registered.
+///
+/// ```rust no_run
+/// # use snurr::{BpmnType, ProcessBuilder};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+/// .task_prefix("rpc.", |input, element| {
+///     println!("call {:?}", element.name);
+///     Default::default()
+/// })
+/// .task_matching(
+///     |element| element.bpmn_type == BpmnType::ServiceTask,
+///     |input, element| Default::default(),
+/// )
+/// .any_task(|input, element| Default::default());
+/// # Ok(())
+/// # }
+/// ```
+impl<T> ProcessBuilder<T> {
+    /// Register a function for every task without a function of its own.
+    pub fn any_task<F>(self, func: F) -> Self
+    where
+        F: Fn(&T, &Element) -> Task + 'static + Sync + Send,
+    {
+        self.task_matching(|_| true, func)
+    }
+
+    /// Register a function for the tasks with a name that starts with the prefix.
+    pub fn task_prefix<F>(self, prefix: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&T, &Element) -> Task + 'static + Sync + Send,
+    {
+        let prefix = prefix.into();
+        self.task_matching(
+            move |element| element.name.is_some_and(|name| name.starts_with(&prefix)),
+            func,
+        )
+    }
+
+    /// Register a function for the tasks accepted by `matches`, for example by type or by a regular
+    /// expression on the name.
+    pub fn task_matching<M, F>(mut self, matches: M, func: F) -> Self
+    where
+        M: Fn(&Element) -> bool + 'static + Sync + Send,
+        F: Fn(&T, &Element) -> Task + 'static + Sync + Send,
+    {
+        let index = self
+            .handler
+            .add_callback(Callback::Element(Box::new(move |data, element| {
+                Ok(func(data, element))
+            })));
+        self.func_map.insert_matcher(Box::new(matches), index);
+        self
+    }
+}
@@ -1,30 +1,82 @@
-use crate::{bpmn::BpmnType, diagram::Id};
+use crate::{api::Element, bpmn::BpmnType, diagram::Id};
 use std::{borrow::Cow, collections::HashMap};
 
-#[derive(Default, Debug)]
+// Registered under a type, an optional subprocess scope and a name or id.
+type Key = (BpmnType, Option<Cow<'static, str>>, Cow<'static, str>);
+
+// Accepts the elements a function is used for.
+type Matcher = Box<dyn Fn(&Element) -> bool + Sync + Send>;
+
+#[derive(Default)]
 pub struct FuncMap {
     // Use `Cow` to avoid creating an owned `String` when comparing.
-    map: HashMap<(BpmnType, Cow<'static, str>), usize>,
+    map: HashMap<Key, usize>,
+    // Matchers with their subprocess scope and function, checked in registration order
+    matchers: Vec<(Option<String>, Matcher, usize)>,
+    // Subprocess name or id used for the functions inserted now
+    scope: Option<String>,
 }
 
 impl FuncMap {
     // Check if bpmn id or name is registered by user. Begin with bpmn id as it is unique and
-    // then try with the name if it exist.
-    pub fn get_id(&self, ty: BpmnType, id: &Id, name: Option<&str>) -> Option<usize> {
-        [Some(id.bpmn()), name]
-            .into_iter()
-            .flatten()
-            .find_map(|s| self.map.get(&(ty, Cow::Borrowed(s))))
+    // then try with the name if it exist. Scopes are the names and ids of the enclosing subprocesses,
+    // innermost first, and are searched before the global functions. In each scope, the types are
+    // tried in order.
+    pub fn get_id(
+        &self,
+        types: &[BpmnType],
+        id: &Id,
+        name: Option<&str>,
+        scopes: &[&str],
+    ) -> Option<usize> {
+        scopes
+            .iter()
+            .map(|scope| Some(Cow::Borrowed(*scope)))
+            .chain([None])
+            .find_map(|scope| {
+                types.iter().find_map(|ty| {
+                    [Some(id.bpmn()), name]
+                        .into_iter()
+                        .flatten()
+                        .find_map(|s| self.map.get(&(*ty, scope.clone(), Cow::Borrowed(s))))
+                })
+            })
             .copied()
     }
 
     pub fn insert(&mut self, ty: BpmnType, name: String, index: usize) {
+        let scope = self.scope.clone().map(Cow::Owned);
         if self
             .map
-            .insert((ty, Cow::Owned(name.clone())), index)
+            .insert((ty, scope, Cow::Owned(name.clone())), index)
             .is_some()
         {
-            log::warn!(r#"Installed {ty} with name "{name}" multiple times"#);
+            match &self.scope {
+                Some(scope) => log::warn!(
+                    r#"Installed {ty} with name "{name}" in subprocess "{scope}" multiple times"#
+                ),
+                None => log::warn!(r#"Installed {ty} with name "{name}" multiple times"#),
+            }
         }
     }
+
+    // First function with a matcher that accepts the element. Matchers in a subprocess scope are only
+    // used for elements in that subprocess.
+    pub fn get_matching(&self, element: &Element, scopes: &[&str]) -> Option<usize> {
+        self.matchers
+            .iter()
+            .find(|(scope, matches, _)| {
+                scope.as_deref().is_none_or(|scope| scopes.contains(&scope)) && matches(element)
+            })
+            .map(|(_, _, index)| *index)
+    }
+
+    pub fn insert_matcher(&mut self, matcher: Matcher, index: usize) {
+        self.matchers.push((self.scope.clone(), matcher, index));
+    }
+
+    // Set the scope of the inserted functions and return the previous one.
+    pub fn set_scope(&mut self, scope: Option<String>) -> Option<String> {
+        std::mem::replace(&mut self.scope, scope)
+    }
 }
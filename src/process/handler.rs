@@ -1,35 +1,98 @@
 use crate::{
     Symbol,
-    api::{Exclusive, Inclusive, IntermediateEvent, Task},
-    process::RuntimeError,
+    api::{CallResult, Element, Exclusive, Inclusive, IntermediateEvent, Task},
+    bpmn::{Activity, Event, Gateway, Mapping},
+    process::{ElementError, RuntimeError},
 };
+use std::{any::Any, sync::Arc};
 
 macro_rules! callback {
-    ($name:ident, $variant:pat => $value:ident, $ret:ty) => {
-        pub(super) fn $name(&self, index: usize, data: &T) -> Result<$ret, RuntimeError> {
-            let Some($variant) = self.callbacks.get(index) else {
+    ($name:ident, $element:ty, $variant:ident, $ret:ident) => {
+        pub(super) fn $name(
+            &self,
+            index: usize,
+            element: &$element,
+            data: &T,
+        ) -> Result<$ret, RuntimeError> {
+            let Some(Callback::$variant(func)) = self.callbacks.get(index) else {
                 Err(RuntimeError::Engine(format!(
                     "missing {} with index: {index}",
                     stringify!($name)
                 )))?
             };
-            Ok($value(data))
+            let element = element.into();
+            match self.intercept(data, &element, &|| {
+                CallResult::$variant(func(data).unwrap_or_else(|error| {
+                    $ret::Panic(Box::new(ElementError::new(&element, error)))
+                }))
+            }) {
+                CallResult::$variant(value) => Ok(value),
+                CallResult::Panic(error) => Ok($ret::Panic(error)),
+                other => Err(mismatch(&element, other)),
+            }
         }
     };
 }
 
-type TaskCallback<T> = Box<dyn Fn(&T) -> Task + Sync + Send>;
-type ExclusiveCallback<T> = Box<dyn Fn(&T) -> Exclusive + Sync + Send>;
-type InclusiveCallback<T> = Box<dyn Fn(&T) -> Inclusive + Sync + Send>;
-type EventBasedCallback<T> = Box<dyn Fn(&T) -> IntermediateEvent + Sync + Send>;
-type EndOrInterMediateCallback<T> = Box<
-    dyn Fn(&T, Option<&str>, Symbol) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
-        + Sync
-        + Send,
->;
+// An interceptor returned the result of another kind of function.
+fn mismatch(element: &Element, result: CallResult) -> RuntimeError {
+    RuntimeError::Engine(format!(
+        "interceptor returned {result:?} for {} `{}`",
+        element.bpmn_type,
+        element.name.unwrap_or(element.id)
+    ))
+}
+
+// Snapshot of the process data published with a job.
+pub(super) type JobData = Arc<dyn Any + Send + Sync>;
+
+pub(super) type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+// Task and gateway functions are fallible, the error is returned with the element.
+type TaskCallback<T> = Box<dyn Fn(&T) -> Result<Task, BoxError> + Sync + Send>;
+type ElementCallback<T> = Box<dyn Fn(&T, &Element) -> Result<Task, BoxError> + Sync + Send>;
+type ExternalCallback<T> = Box<dyn Fn(&T) -> JobData + Sync + Send>;
+type ExclusiveCallback<T> = Box<dyn Fn(&T) -> Result<Exclusive, BoxError> + Sync + Send>;
+type InclusiveCallback<T> = Box<dyn Fn(&T) -> Result<Inclusive, BoxError> + Sync + Send>;
+type EventBasedCallback<T> = Box<dyn Fn(&T) -> Result<IntermediateEvent, BoxError> + Sync + Send>;
+type EndOrInterMediateCallback<T> =
+    Box<dyn Fn(&T, Option<&str>, Symbol) -> Result<(), BoxError> + Sync + Send>;
+type ErrorBoundaryCallback = Box<dyn Fn(&ElementError) -> Option<Task> + Sync + Send>;
+
+type InterceptCallback<T> =
+    Box<dyn Fn(&T, &Element, &dyn Fn() -> CallResult) -> CallResult + Sync + Send>;
+
+type ForkCallback<T> = Box<dyn Fn(&T) -> T + Sync + Send>;
+type MergeCallback<T> = Box<dyn Fn(&str, Vec<T>) -> T + Sync + Send>;
+
+// Branch-local data. Forked tokens get a copy of the data that is merged at the join.
+pub(super) struct Branches<T> {
+    pub(super) fork: ForkCallback<T>,
+    pub(super) merge: MergeCallback<T>,
+}
+
+type EnterCallback<T> = Box<dyn Fn(&T, Option<&Mapping>) -> T + Sync + Send>;
+type LeaveCallback<T> = Box<dyn Fn(&T, &T, &Mapping) + Sync + Send>;
+
+// Scoped data. Subprocesses and activities with a mapping get data of their own, and the mapped
+// outputs are copied back when they complete.
+pub(super) struct Scopes<T> {
+    pub(super) enter: EnterCallback<T>,
+    pub(super) leave: LeaveCallback<T>,
+}
+
+// Task completed outside the process instance.
+pub(super) enum Delegated {
+    Job(JobData),
+    UserTask,
+}
 
 pub(super) enum Callback<T> {
     Task(TaskCallback<T>),
+    // Task function shared by many tasks, called with the task
+    Element(ElementCallback<T>),
+    External(ExternalCallback<T>),
+    Human,
     Exclusive(ExclusiveCallback<T>),
     Inclusive(InclusiveCallback<T>),
     EventBased(EventBasedCallback<T>),
@@ -38,12 +101,22 @@ pub(super) enum Callback<T> {
 
 pub(super) struct Handler<T> {
     callbacks: Vec<Callback<T>>,
+    // Wrap every function call, the first registered is the outermost
+    interceptors: Vec<InterceptCallback<T>>,
+    // Task errors mapped to a boundary
+    error_boundary: Option<ErrorBoundaryCallback>,
+    branches: Option<Branches<T>>,
+    scopes: Option<Scopes<T>>,
 }
 
 impl<T> Default for Handler<T> {
     fn default() -> Self {
         Self {
             callbacks: Default::default(),
+            interceptors: Default::default(),
+            error_boundary: None,
+            branches: None,
+            scopes: None,
         }
     }
 }
@@ -56,23 +129,114 @@ impl<T> Handler<T> {
         index
     }
 
-    callback!(run_task, Callback::Task(func) => func, Task);
-    callback!(run_exclusive, Callback::Exclusive(func) => func, Exclusive);
-    callback!(run_inclusive, Callback::Inclusive(func) => func, Inclusive);
-    callback!(run_eventbased, Callback::EventBased(func) => func, IntermediateEvent);
+    pub(super) fn add_interceptor(&mut self, interceptor: InterceptCallback<T>) {
+        self.interceptors.push(interceptor);
+    }
+
+    // Call the function through the interceptors
+    fn intercept(&self, data: &T, element: &Element, call: &dyn Fn() -> CallResult) -> CallResult {
+        fn chain<T>(
+            interceptors: &[InterceptCallback<T>],
+            data: &T,
+            element: &Element,
+            call: &dyn Fn() -> CallResult,
+        ) -> CallResult {
+            match interceptors.split_first() {
+                Some((interceptor, inner)) => {
+                    interceptor(data, element, &|| chain(inner, data, element, call))
+                }
+                None => call(),
+            }
+        }
+        chain(&self.interceptors, data, element, call)
+    }
+
+    pub(super) fn set_error_boundary(&mut self, error_boundary: ErrorBoundaryCallback) {
+        self.error_boundary = Some(error_boundary);
+    }
+
+    // The error of a task follows the error boundary if one is selected, otherwise the process panics.
+    fn task_failed(&self, element: &Element, error: BoxError) -> Task {
+        let error = ElementError::new(element, error);
+        self.error_boundary
+            .as_ref()
+            .and_then(|error_boundary| error_boundary(&error))
+            .unwrap_or_else(|| Task::Panic(Box::new(error)))
+    }
+
+    pub(super) fn set_branches(&mut self, branches: Branches<T>) {
+        self.branches = Some(branches);
+    }
+
+    pub(super) fn branches(&self) -> Option<&Branches<T>> {
+        self.branches.as_ref()
+    }
+
+    pub(super) fn set_scopes(&mut self, scopes: Scopes<T>) {
+        self.scopes = Some(scopes);
+    }
+
+    pub(super) fn scopes(&self) -> Option<&Scopes<T>> {
+        self.scopes.as_ref()
+    }
+
+    pub(super) fn run_task(
+        &self,
+        index: usize,
+        activity: &Activity,
+        data: &T,
+    ) -> Result<Task, RuntimeError> {
+        let element = activity.into();
+        let task = |result: Result<Task, BoxError>| {
+            CallResult::Task(result.unwrap_or_else(|error| self.task_failed(&element, error)))
+        };
+        let result = match self.callbacks.get(index) {
+            Some(Callback::Task(func)) => self.intercept(data, &element, &|| task(func(data))),
+            Some(Callback::Element(func)) => {
+                self.intercept(data, &element, &|| task(func(data, &element)))
+            }
+            _ => Err(RuntimeError::Engine(format!(
+                "missing run_task with index: {index}"
+            )))?,
+        };
+        match result {
+            CallResult::Task(task) => Ok(task),
+            CallResult::Panic(error) => Ok(Task::Panic(error)),
+            other => Err(mismatch(&element, other)),
+        }
+    }
+
+    // How the task is completed if it is handled by external workers or people.
+    pub(super) fn delegated(&self, index: usize, data: &T) -> Option<Delegated> {
+        match self.callbacks.get(index) {
+            Some(Callback::External(func)) => Some(Delegated::Job(func(data))),
+            Some(Callback::Human) => Some(Delegated::UserTask),
+            _ => None,
+        }
+    }
+
+    callback!(run_exclusive, Gateway, Exclusive, Exclusive);
+    callback!(run_inclusive, Gateway, Inclusive, Inclusive);
+    callback!(run_eventbased, Gateway, EventBased, IntermediateEvent);
 
     pub(super) fn run_end_or_intermediate(
         &self,
         index: usize,
+        event: &Event,
         data: &T,
-        name: Option<&str>,
-        symbol: Symbol,
-    ) -> Result<Result<(), Box<dyn std::error::Error + Send + Sync>>, RuntimeError> {
+    ) -> Result<Result<(), BoxError>, RuntimeError> {
         let Some(Callback::EndOrIntermediate(func)) = self.callbacks.get(index) else {
             Err(RuntimeError::Engine(format!(
                 "missing run_end_or_intermediate with index: {index}",
             )))?
         };
-        Ok(func(data, name, symbol))
+        let element = event.into();
+        match self.intercept(data, &element, &|| {
+            CallResult::Event(func(data, event.name.as_deref(), event.symbol))
+        }) {
+            CallResult::Event(result) => Ok(result),
+            CallResult::Panic(error) => Ok(Err(error)),
+            other => Err(mismatch(&element, other)),
+        }
     }
 }
@@ -0,0 +1,93 @@
+use super::{RuntimeError, lock};
+use log::debug;
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard},
+};
+
+/// User task waiting in the inbox of an [`Engine`](crate::Engine) until a person completes it.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct InboxItem {
+    pub id: usize,
+    /// Name of the task, or its id when unnamed
+    pub name: String,
+    /// Bpmn id of the task
+    pub element_id: String,
+    /// Documentation of the task in the diagram
+    pub documentation: Option<String>,
+    /// Name of the lane containing the task
+    pub lane: Option<String>,
+    /// Assignee from the diagram, or the user that claimed the item or it was reassigned to
+    pub assignee: Option<String>,
+    /// Id of the instance waiting on the item
+    pub instance: usize,
+}
+
+// User tasks published by the instances of an engine until they are completed.
+#[derive(Default)]
+pub(super) struct Inbox {
+    items: Mutex<BTreeMap<usize, InboxItem>>,
+}
+
+impl Inbox {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<usize, InboxItem>> {
+        lock(&self.items)
+    }
+
+    pub(super) fn publish(&self, item: InboxItem) {
+        debug!(
+            "instance {} added {} to the inbox",
+            item.instance, item.name
+        );
+        self.lock().insert(item.id, item);
+    }
+
+    pub(super) fn list(&self) -> Vec<InboxItem> {
+        self.lock().values().cloned().collect()
+    }
+
+    // Assign the unassigned item to the user.
+    pub(super) fn claim(&self, id: usize, user: &str) -> Result<(), RuntimeError> {
+        let mut items = self.lock();
+        let item = items
+            .get_mut(&id)
+            .ok_or(RuntimeError::UnknownUserTask(id))?;
+        match &item.assignee {
+            Some(assignee) if assignee != user => Err(RuntimeError::NotAssigned(id, user.into())),
+            _ => {
+                item.assignee = Some(user.to_string());
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn reassign(&self, id: usize, user: Option<&str>) -> Result<(), RuntimeError> {
+        self.lock()
+            .get_mut(&id)
+            .ok_or(RuntimeError::UnknownUserTask(id))?
+            .assignee = user.map(Into::into);
+        Ok(())
+    }
+
+    // Remove and return the item assigned to the user, so it is completed only once.
+    pub(super) fn take_assigned(&self, id: usize, user: &str) -> Result<InboxItem, RuntimeError> {
+        let mut items = self.lock();
+        match items.get(&id) {
+            None => Err(RuntimeError::UnknownUserTask(id)),
+            Some(InboxItem { assignee, .. }) if assignee.as_deref() != Some(user) => {
+                Err(RuntimeError::NotAssigned(id, user.into()))
+            }
+            Some(_) => Ok(items.remove(&id).unwrap()),
+        }
+    }
+
+    pub(super) fn remove(&self, id: usize) {
+        self.lock().remove(&id);
+    }
+
+    // Remove the items of an instance that has ended.
+    pub(super) fn remove_instance(&self, instance: usize) {
+        self.lock().retain(|_, item| item.instance != instance);
+    }
+}
@@ -0,0 +1,34 @@
+use super::ProcessBuilder;
+use crate::api::{CallResult, Element};
+
+impl<T> ProcessBuilder<T> {
+    /// Register an interceptor that wraps every call to a task, gateway or event function. It is called with
+    /// the element and a function that calls the next interceptor or the registered function, and returns the
+    /// result. Skip the call and return [`CallResult::Panic`], or a boundary for a task, to short-circuit it.
+    /// The first registered interceptor is the outermost. External and human tasks are not intercepted.
+    ///
+    /// ```rust no_run
+    /// # use snurr::{CallResult, ProcessBuilder};
+    /// # use std::time::Instant;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+    /// .intercept(|input, element, next| {
+    ///     if element.name == Some("Forbidden") {
+    ///         return CallResult::panic("not authorized");
+    ///     }
+    ///     let start = Instant::now();
+    ///     let result = next();
+    ///     println!("{} took {:?} with {result:?}", element.id, start.elapsed());
+    ///     result
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn intercept<F>(mut self, func: F) -> Self
+    where
+        F: Fn(&T, &Element, &dyn Fn() -> CallResult) -> CallResult + 'static + Sync + Send,
+    {
+        self.handler.add_interceptor(Box::new(func));
+        self
+    }
+}
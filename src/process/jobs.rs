@@ -0,0 +1,109 @@
+use super::{RuntimeError, handler::JobData, lock};
+use log::debug;
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    fmt::{self, Debug, Formatter},
+    sync::{Mutex, MutexGuard},
+};
+
+/// Work published for an external task, fetched by workers with [`Engine::fetch_and_lock`](crate::Engine::fetch_and_lock).
+#[derive(Clone)]
+#[non_exhaustive]
+pub struct Job {
+    pub id: usize,
+    /// Name of the task, or its id when unnamed
+    pub topic: String,
+    /// Bpmn id of the task
+    pub element_id: String,
+    /// Id of the instance waiting on the job
+    pub instance: usize,
+    data: JobData,
+}
+
+impl Job {
+    /// Snapshot of the process data taken when the job was published.
+    /// Returns None if the snapshot is not a `D`.
+    pub fn data<D: Any>(&self) -> Option<&D> {
+        self.data.downcast_ref()
+    }
+}
+
+impl Debug for Job {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Job")
+            .field("id", &self.id)
+            .field("topic", &self.topic)
+            .field("element_id", &self.element_id)
+            .field("instance", &self.instance)
+            .finish_non_exhaustive()
+    }
+}
+
+// Jobs published by the instances of an engine until a worker completes or fails them.
+#[derive(Default)]
+pub(super) struct Jobs {
+    // Jobs with the worker that locked them
+    entries: Mutex<BTreeMap<usize, (Job, Option<String>)>>,
+}
+
+impl Jobs {
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<usize, (Job, Option<String>)>> {
+        lock(&self.entries)
+    }
+
+    pub(super) fn publish(
+        &self,
+        id: usize,
+        instance: usize,
+        topic: &str,
+        element_id: &str,
+        data: JobData,
+    ) {
+        debug!("instance {instance} published job {id} on {topic}");
+        self.lock().insert(
+            id,
+            (
+                Job {
+                    id,
+                    topic: topic.to_string(),
+                    element_id: element_id.to_string(),
+                    instance,
+                    data,
+                },
+                None,
+            ),
+        );
+    }
+
+    // Lock at most `max` unlocked jobs on the topic to the worker, oldest first.
+    pub(super) fn fetch_and_lock(&self, topic: &str, worker: &str, max: usize) -> Vec<Job> {
+        self.lock()
+            .values_mut()
+            .filter(|(job, locked)| locked.is_none() && job.topic == topic)
+            .take(max)
+            .map(|(job, locked)| {
+                *locked = Some(worker.to_string());
+                job.clone()
+            })
+            .collect()
+    }
+
+    // Remove and return the job locked by the worker, so it is finished only once.
+    pub(super) fn take_locked(&self, id: usize, worker: &str) -> Result<Job, RuntimeError> {
+        let mut jobs = self.lock();
+        match jobs.get(&id) {
+            Some((_, Some(locked))) if locked == worker => Ok(jobs.remove(&id).unwrap().0),
+            _ => Err(RuntimeError::UnknownJob(id)),
+        }
+    }
+
+    pub(super) fn remove(&self, id: usize) {
+        self.lock().remove(&id);
+    }
+
+    // Remove the jobs of an instance that has ended.
+    pub(super) fn remove_instance(&self, instance: usize) {
+        self.lock().retain(|_, (job, _)| job.instance != instance);
+    }
+}
@@ -0,0 +1,437 @@
+use super::{lock, runtime::Work};
+use crate::{
+    api::Task,
+    clock::{Clock, WakeId},
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::SystemTime,
+};
+
+type Listener = Box<dyn Fn(&str) + Send>;
+
+// Applied to the process data when a message, job or user task completion is caught. Jobs and user tasks continue
+// with the returned task result.
+pub(super) type Payload<T> = Box<dyn FnOnce(&T) -> Task + Send>;
+
+// Something a waiting token can catch.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Trigger<'a> {
+    Signal(&'a str),
+    Message(&'a str),
+    // Timer armed with `Mailbox::arm`
+    Timer(usize),
+    // Job or user task published on the work of the instance
+    Work(usize),
+}
+
+// Signals, messages and timers delivered to the tokens of one running process instance.
+pub(super) struct Mailbox<T> {
+    shared: Arc<Shared>,
+    // Payloads of messages and jobs not caught yet by sequence number
+    payloads: Mutex<HashMap<usize, Payload<T>>>,
+    // Jobs and user tasks of the engine, with the id of the instance
+    work: Option<(usize, Arc<Work>)>,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    // Sequence number of the next arrival. Used to catch the first arrival.
+    sequence: usize,
+    // Messages can be delivered from outside the instance, so it never stalls.
+    external: bool,
+    // Signals thrown in the instance with their sequence number. Dropped once no execution loop can catch them.
+    signals: Vec<(usize, String)>,
+    // Lowest sequence number of a signal that each execution loop can still catch.
+    scopes: BTreeMap<usize, usize>,
+    next_scope: usize,
+    // Messages not caught yet with their sequence number.
+    messages: Vec<(usize, String)>,
+    // Timers armed that have not fired or been withdrawn.
+    timers: HashSet<usize>,
+    // Wake ups of the armed timers, cancelled when the timer is withdrawn.
+    wakes: HashMap<usize, (Arc<dyn Clock>, WakeId)>,
+    // Timers fired with their sequence number.
+    fired: HashMap<usize, usize>,
+    // Jobs and user tasks completed with their sequence number.
+    done: HashMap<usize, usize>,
+    next_timer: usize,
+    // Tokens that make progress. When zero, nothing in the instance can throw a signal.
+    running: usize,
+    // Execution loops blocked until something arrives.
+    blocked: usize,
+    next_listener: usize,
+    listeners: BTreeMap<usize, Listener>,
+}
+
+impl State {
+    fn arrived(&mut self) -> usize {
+        self.sequence += 1;
+        self.sequence - 1
+    }
+
+    fn prune_signals(&mut self) {
+        match self.scopes.values().min() {
+            Some(lowest) => {
+                let lowest = *lowest;
+                self.signals.retain(|(sequence, _)| *sequence >= lowest);
+            }
+            None => self.signals.clear(),
+        }
+    }
+
+    // Forget the timer and return its wake up to cancel once the lock is released.
+    fn withdraw(&mut self, id: usize) -> Option<(Arc<dyn Clock>, WakeId)> {
+        self.timers.remove(&id);
+        self.fired.remove(&id);
+        self.wakes.remove(&id)
+    }
+}
+
+fn cancel(wakes: impl IntoIterator<Item = (Arc<dyn Clock>, WakeId)>) {
+    wakes.into_iter().for_each(|(clock, id)| clock.cancel(id));
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+
+    fn fire(&self, id: usize) {
+        let mut state = self.lock();
+        state.wakes.remove(&id);
+        if state.timers.remove(&id) {
+            let sequence = state.arrived();
+            state.fired.insert(id, sequence);
+            self.changed.notify_all();
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn stopped(&self) {
+        let mut state = self.lock();
+        state.running -= 1;
+        if state.running == 0 {
+            // Let waiting tokens find out that they are stalled
+            self.changed.notify_all();
+        }
+    }
+}
+
+impl<T> Default for Mailbox<T> {
+    fn default() -> Self {
+        Self {
+            shared: Default::default(),
+            payloads: Default::default(),
+            work: None,
+        }
+    }
+}
+
+impl<T> Mailbox<T> {
+    // Mailbox for an instance owned by a runtime, that waits for messages delivered from outside.
+    // External tasks and user tasks are published on the work of an engine with the instance id.
+    pub(super) fn external(work: Option<(usize, Arc<Work>)>) -> Self {
+        let mailbox = Self {
+            shared: Default::default(),
+            payloads: Default::default(),
+            work,
+        };
+        mailbox.lock().external = true;
+        mailbox
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.shared.lock()
+    }
+
+    pub(super) fn is_external(&self) -> bool {
+        self.lock().external
+    }
+
+    // Sequence number of the next arrival.
+    pub(super) fn sequence(&self) -> usize {
+        self.lock().sequence
+    }
+
+    // Broadcast a signal to everything waiting on it.
+    pub(super) fn signal(&self, name: &str) {
+        let mut state = self.lock();
+        let sequence = state.arrived();
+        state.signals.push((sequence, name.to_string()));
+        state.listeners.values().for_each(|listener| listener(name));
+        self.shared.changed.notify_all();
+    }
+
+    // Arm a timer that can be caught with `Trigger::Timer` once the clock has reached `deadline`.
+    pub(super) fn arm(&self, clock: &Arc<dyn Clock>, deadline: SystemTime) -> usize {
+        let id = {
+            let mut state = self.lock();
+            state.next_timer += 1;
+            let id = state.next_timer;
+            state.timers.insert(id);
+            id
+        };
+
+        // Lock must not be held, the clock might wake immediately
+        let shared = Arc::clone(&self.shared);
+        let wake = clock.wake_at(deadline, Box::new(move || shared.fire(id)));
+        let mut state = self.lock();
+        if state.timers.contains(&id) {
+            state.wakes.insert(id, (Arc::clone(clock), wake));
+        }
+        id
+    }
+
+    // Withdraw the timers, jobs and user tasks of a token that no longer waits on them.
+    pub(super) fn withdraw(&self, triggers: &[Trigger]) {
+        let wakes: Vec<_> = {
+            let mut state = self.lock();
+            triggers
+                .iter()
+                .filter_map(|trigger| match trigger {
+                    Trigger::Timer(id) => state.withdraw(*id),
+                    Trigger::Work(id) => {
+                        state.done.remove(id);
+                        None
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        cancel(wakes);
+
+        if let Some((_, work)) = &self.work {
+            triggers.iter().for_each(|trigger| {
+                if let Trigger::Work(id) = trigger {
+                    work.withdraw(*id);
+                }
+            });
+        }
+    }
+
+    // Deliver a message to one token waiting on it. Kept until caught.
+    pub(super) fn message(&self, name: &str, payload: Payload<T>) {
+        let mut payloads = lock(&self.payloads);
+        let mut state = self.lock();
+        let sequence = state.arrived();
+        payloads.insert(sequence, payload);
+        state.messages.push((sequence, name.to_string()));
+        self.shared.changed.notify_all();
+    }
+
+    // Instance id and the work to publish on, that is caught with `Trigger::Work` once completed.
+    // Returns None if the instance is not run by an engine.
+    pub(super) fn work(&self) -> Option<(usize, &Work)> {
+        self.work
+            .as_ref()
+            .map(|(instance, work)| (*instance, work.as_ref()))
+    }
+
+    // Deliver the completion or failure of a job or user task to the token waiting on it.
+    // Returns false if it has already been completed.
+    pub(super) fn complete(&self, work: usize, payload: Payload<T>) -> bool {
+        let mut payloads = lock(&self.payloads);
+        let mut state = self.lock();
+        if state.done.contains_key(&work) {
+            return false;
+        }
+        let sequence = state.arrived();
+        payloads.insert(sequence, payload);
+        state.done.insert(work, sequence);
+        self.shared.changed.notify_all();
+        true
+    }
+
+    // Register an execution loop that catches signals from sequence number `since`.
+    pub(super) fn enter(&self, since: usize) -> usize {
+        let mut state = self.lock();
+        state.next_scope += 1;
+        let scope = state.next_scope;
+        state.scopes.insert(scope, since);
+        scope
+    }
+
+    // The execution loop only catches signals from sequence number `since` from now on.
+    pub(super) fn advance(&self, scope: usize, since: usize) {
+        let mut state = self.lock();
+        state.scopes.insert(scope, since);
+        state.prune_signals();
+    }
+
+    pub(super) fn leave(&self, scope: usize) {
+        let mut state = self.lock();
+        state.scopes.remove(&scope);
+        state.prune_signals();
+    }
+
+    // The instance has ended. Drop messages, completions and signals that were never caught.
+    pub(super) fn clear(&self) {
+        let mut payloads = lock(&self.payloads);
+        let mut state = self.lock();
+        payloads.clear();
+        state.messages.clear();
+        state.done.clear();
+        state.signals.clear();
+    }
+
+    // Signals thrown from sequence number `since`.
+    pub(super) fn signals(&self, since: usize) -> Vec<String> {
+        self.lock()
+            .signals
+            .iter()
+            .filter(|(sequence, _)| *sequence >= since)
+            .map(|(_, name)| name.clone())
+            .collect()
+    }
+
+    // Catch the trigger that arrived first and return its position, with the payload of a caught message.
+    // Signals are caught from sequence number `since`, while messages are caught even if they arrived before.
+    // The timers of the other triggers are withdrawn.
+    pub(super) fn catch(
+        &self,
+        since: usize,
+        triggers: &[Trigger],
+    ) -> Option<(usize, Option<Payload<T>>)> {
+        let mut payloads = lock(&self.payloads);
+        let mut state = self.lock();
+        let (position, _) = triggers
+            .iter()
+            .enumerate()
+            .filter_map(|(position, trigger)| {
+                let sequence = match trigger {
+                    Trigger::Signal(name) => state
+                        .signals
+                        .iter()
+                        .find(|(sequence, signal)| *sequence >= since && signal == name)
+                        .map(|(sequence, _)| *sequence),
+                    Trigger::Message(name) => state
+                        .messages
+                        .iter()
+                        .find(|(_, message)| message == name)
+                        .map(|(sequence, _)| *sequence),
+                    Trigger::Timer(id) => state.fired.get(id).copied(),
+                    Trigger::Work(id) => state.done.get(id).copied(),
+                };
+                Some((position, sequence?))
+            })
+            .min_by_key(|(_, sequence)| *sequence)?;
+
+        let mut payload = None;
+        if let Trigger::Message(name) = triggers[position]
+            && let Some(index) = state
+                .messages
+                .iter()
+                .position(|(_, message)| message == name)
+        {
+            let (sequence, _) = state.messages.remove(index);
+            payload = payloads.remove(&sequence);
+        }
+        if let Trigger::Work(id) = triggers[position]
+            && let Some(sequence) = state.done.remove(&id)
+        {
+            payload = payloads.remove(&sequence);
+        }
+
+        let wakes: Vec<_> = triggers
+            .iter()
+            .filter_map(|trigger| match trigger {
+                Trigger::Timer(id) => state.withdraw(*id),
+                _ => None,
+            })
+            .collect();
+        drop(state);
+        cancel(wakes);
+        Some((position, payload))
+    }
+
+    // Call the listener for every signal until it is removed. The listener must not block.
+    pub(super) fn listen(&self, listener: Listener) -> usize {
+        let mut state = self.lock();
+        state.next_listener += 1;
+        let id = state.next_listener;
+        state.listeners.insert(id, listener);
+        id
+    }
+
+    pub(super) fn unlisten(&self, id: usize) {
+        self.lock().listeners.remove(&id);
+    }
+
+    // Block until something arrives after sequence number `seen`. Returns false if no token
+    // is running, no timer is armed and no message can be delivered, so nothing can arrive.
+    pub(super) fn wait(&self, seen: usize) -> bool {
+        let mut state = self.lock();
+        state.blocked += 1;
+        let arrived = loop {
+            if state.sequence > seen {
+                break true;
+            }
+            if state.running == 0 && state.timers.is_empty() && !state.external {
+                break false;
+            }
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        };
+        state.blocked -= 1;
+        arrived
+    }
+
+    // No token makes progress and the instance waits for something to arrive.
+    pub(super) fn is_waiting(&self) -> bool {
+        let state = self.lock();
+        state.blocked > 0 && state.running == 0
+    }
+
+    // Count a token as running until the guard is dropped.
+    #[cfg(feature = "parallel")]
+    pub(super) fn running(&self) -> Running<'_> {
+        self.lock().running += 1;
+        Running(&self.shared)
+    }
+
+    // Stop counting a token as running until the guard is dropped, used while it waits on a subprocess.
+    #[cfg(feature = "parallel")]
+    pub(super) fn suspend(&self) -> Suspended<'_> {
+        self.shared.stopped();
+        Suspended(&self.shared)
+    }
+}
+
+impl<T> Drop for Mailbox<T> {
+    fn drop(&mut self) {
+        // The instance has ended, timers still armed will never be caught
+        let wakes = std::mem::take(&mut self.lock().wakes);
+        cancel(wakes.into_values());
+    }
+}
+
+#[cfg(feature = "parallel")]
+pub(super) struct Running<'a>(&'a Shared);
+
+#[cfg(feature = "parallel")]
+impl Drop for Running<'_> {
+    fn drop(&mut self) {
+        self.0.stopped();
+    }
+}
+
+#[cfg(feature = "parallel")]
+pub(super) struct Suspended<'a>(&'a Shared);
+
+#[cfg(feature = "parallel")]
+impl Drop for Suspended<'_> {
+    fn drop(&mut self) {
+        self.0.lock().running += 1;
+    }
+}
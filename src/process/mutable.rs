@@ -0,0 +1,118 @@
+use super::{Process, ProcessBuilder, RuntimeError, lock};
+use crate::{
+    api::{Exclusive, Inclusive, IntermediateEvent, Task},
+    error::BpmnFileError,
+};
+use std::{
+    path::Path,
+    sync::{Mutex, PoisonError},
+};
+
+// Call the `FnMut` with mutable access to the data. Callbacks run one at a time.
+fn with_mut<D, R>(
+    mut func: impl FnMut(&mut D) -> R + Send + 'static,
+) -> impl Fn(&Mutex<D>) -> R + Send + Sync + 'static
+where
+    D: Send,
+{
+    let func = Mutex::new(move |data: &mut D| func(data));
+    move |data| {
+        let mut data = lock(data);
+        lock(&func)(&mut data)
+    }
+}
+
+impl<D> ProcessBuilder<D> {
+    /// Creates a process builder like [`ProcessBuilder::new`] for plain data `D`, kept in a
+    /// `Mutex`. Register the callbacks with the `_mut` functions.
+    pub fn new_mut(path: impl AsRef<Path>) -> Result<ProcessBuilder<Mutex<D>>, BpmnFileError> {
+        ProcessBuilder::new(path)
+    }
+}
+
+/// Callbacks with mutable access to the process data, for models that are not `Sync`.
+///
+/// The data is kept in a `Mutex` that is locked while a callback runs, so callbacks take `&mut D`
+/// and may be `FnMut`. Run the process with [`Process::run_mut`] to pass and get back a plain `D`.
+///
+/// A task interrupted by a boundary event keeps running to its end and holds the lock until
+/// then, so the callbacks after the boundary wait for it.
+///
+/// ```rust no_run
+/// use snurr::ProcessBuilder;
+///
+/// #[derive(Debug, Default)]
+/// struct Counter {
+///     count: u32,
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let mut calls = 0;
+///     let bpmn = ProcessBuilder::<Counter>::new_mut("examples/counter.bpmn")?
+///         .task_mut("Count 1", move |input| {
+///             calls += 1;
+///             input.count += 1;
+///             Default::default()
+///         })
+///         .exclusive_mut("equal to 3", |input| {
+///             match input.count {
+///                 3 => "YES",
+///                 _ => "NO",
+///             }
+///             .into()
+///         })
+///         .build()?;
+///
+///     let result = bpmn.run_mut(Counter::default())?;
+///     println!("{result:?}");
+///     Ok(())
+/// }
+/// ```
+impl<D> ProcessBuilder<Mutex<D>>
+where
+    D: Send + 'static,
+{
+    /// Register a task like [`ProcessBuilder::task`] with mutable access to the data.
+    pub fn task_mut<F>(self, name: impl Into<String>, func: F) -> Self
+    where
+        F: FnMut(&mut D) -> Task + 'static + Send,
+    {
+        self.task(name, with_mut(func))
+    }
+
+    /// Register an exclusive gateway like [`ProcessBuilder::exclusive`] with mutable access to the data.
+    pub fn exclusive_mut<F>(self, name: impl Into<String>, func: F) -> Self
+    where
+        F: FnMut(&mut D) -> Exclusive + 'static + Send,
+    {
+        self.exclusive(name, with_mut(func))
+    }
+
+    /// Register an inclusive gateway like [`ProcessBuilder::inclusive`] with mutable access to the data.
+    pub fn inclusive_mut<F>(self, name: impl Into<String>, func: F) -> Self
+    where
+        F: FnMut(&mut D) -> Inclusive + 'static + Send,
+    {
+        self.inclusive(name, with_mut(func))
+    }
+
+    /// Register an event-based gateway like [`ProcessBuilder::event_based`] with mutable access to the data.
+    pub fn event_based_mut<F>(self, name: impl Into<String>, func: F) -> Self
+    where
+        F: FnMut(&mut D) -> IntermediateEvent + 'static + Send,
+    {
+        self.event_based(name, with_mut(func))
+    }
+}
+
+impl<D> Process<Mutex<D>>
+where
+    D: Send,
+{
+    /// Run the process with plain data, registered with the `_mut` functions of the builder,
+    /// and return the data or a `RuntimeError`.
+    pub fn run_mut(&self, data: D) -> Result<D, RuntimeError> {
+        self.run(Mutex::new(data))
+            .map(|data| data.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+}
@@ -0,0 +1,101 @@
+use super::{ProcessBuilder, handler::Callback};
+use crate::{api::Task, bpmn::BpmnType};
+
+// Register the function for one activity type. It is found before a function registered with `task`.
+macro_rules! typed_task {
+    ($(#[$doc:meta])* $method:ident, $ty:ident) => {
+        $(#[$doc])*
+        pub fn $method<F>(mut self, name: impl Into<String>, func: F) -> Self
+        where
+            F: Fn(&T) -> Task + 'static + Sync + Send,
+        {
+            let index = self
+                .handler
+                .add_callback(Callback::Task(Box::new(move |data| Ok(func(data)))));
+            self.func_map.insert(BpmnType::$ty, name.into(), index);
+            self
+        }
+    };
+}
+
+/// Functions registered for an activity type are used before functions registered with
+/// [`ProcessBuilder::task`] with the same name or id.
+///
+/// ```rust no_run
+/// # use snurr::ProcessBuilder;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+/// .service_task("Notify", |input| Default::default())
+/// .send_task("Notify", |input| Default::default())
+/// .task("Notify", |input| Default::default());
+/// # Ok(())
+/// # }
+/// ```
+impl<T> ProcessBuilder<T> {
+    typed_task!(
+        /// Register a function for service tasks with the name or id.
+        service_task,
+        ServiceTask
+    );
+    typed_task!(
+        /// Register a function for user tasks with the name or id. See [`ProcessBuilder::human_task`] to
+        /// complete them outside of the process.
+        user_task,
+        UserTask
+    );
+    typed_task!(
+        /// Register a function for send tasks with the name or id.
+        send_task,
+        SendTask
+    );
+    typed_task!(
+        /// Register a function for receive tasks with the name or id. It runs when the message has arrived.
+        receive_task,
+        ReceiveTask
+    );
+    typed_task!(
+        /// Register a function for script tasks with the name or id.
+        script_task,
+        ScriptTask
+    );
+    typed_task!(
+        /// Register a function for manual tasks with the name or id.
+        manual_task,
+        ManualTask
+    );
+    typed_task!(
+        /// Register a function for business rule tasks with the name or id.
+        business_rule_task,
+        BusinessRuleTask
+    );
+    typed_task!(
+        /// Register a function for call activities with the name or id.
+        call_activity,
+        CallActivity
+    );
+
+    /// Register functions that are only used by the elements of the subprocess with the name or id,
+    /// including its nested subprocesses. Elements of the subprocess without a scoped function use
+    /// the functions registered for an enclosing subprocess or for the whole process.
+    ///
+    /// ```rust no_run
+    /// # use snurr::ProcessBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// #   ProcessBuilder::<()>::new("dummy.bpmn")?
+    /// .in_subprocess("Shipping", |builder| {
+    ///     builder.task("Calculate", |input| Default::default())
+    /// })
+    /// .task("Calculate", |input| Default::default());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn in_subprocess<F>(mut self, name: impl Into<String>, register: F) -> Self
+    where
+        F: FnOnce(Self) -> Self,
+    {
+        let outer = self.func_map.set_scope(Some(name.into()));
+        let mut builder = register(self);
+        builder.func_map.set_scope(outer);
+        builder
+    }
+}
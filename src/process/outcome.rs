@@ -0,0 +1,55 @@
+use crate::bpmn::{Event, Symbol};
+
+/// End event reached by a process run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EndEvent {
+    pub id: String,
+    pub name: Option<String>,
+    pub symbol: Symbol,
+}
+
+impl From<&Event> for EndEvent {
+    fn from(event: &Event) -> Self {
+        Self {
+            id: event.id.bpmn().into(),
+            name: event.name.clone(),
+            symbol: event.symbol,
+        }
+    }
+}
+
+/// How a process run ended, returned by `Process::run_with_outcome`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Outcome {
+    /// End events reached in the main process in the order they were visited.
+    pub end_events: Vec<EndEvent>,
+}
+
+impl Outcome {
+    pub(super) fn new(end_events: &[&Event]) -> Self {
+        Self {
+            end_events: end_events.iter().map(|event| (*event).into()).collect(),
+        }
+    }
+
+    /// True if an end event with the name or id was reached.
+    pub fn reached(&self, name_or_id: &str) -> bool {
+        self.end_events
+            .iter()
+            .any(|end| end.id == name_or_id || end.name.as_deref() == Some(name_or_id))
+    }
+
+    /// True if an end event with the symbol was reached.
+    pub fn reached_symbol(&self, symbol: Symbol) -> bool {
+        self.end_events.iter().any(|end| end.symbol == symbol)
+    }
+
+    /// True if the run ended with a terminate end event.
+    pub fn terminated(&self) -> bool {
+        self.end_events
+            .last()
+            .is_some_and(|end| end.symbol == Symbol::Terminate)
+    }
+}
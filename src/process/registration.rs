@@ -0,0 +1,157 @@
+use super::ProcessBuilder;
+use crate::api::{Exclusive, FlowChoice, Inclusive, IntermediateEvent, Task};
+use std::sync::Arc;
+
+/// Handlers of a process implemented on a struct and registered in one call with
+/// [`ProcessBuilder::handlers`]. The handlers are shared by all registered functions, so they can hold
+/// configuration such as clients and caches.
+///
+/// ```rust no_run
+/// use snurr::{ProcessBuilder, ProcessHandlers, Registration, Task};
+/// use std::sync::atomic::{AtomicU32, Ordering::Relaxed};
+///
+/// struct Counter {
+///     step: u32,
+/// }
+///
+/// impl Counter {
+///     fn count(&self, input: &AtomicU32) -> Task {
+///         input.fetch_add(self.step, Relaxed);
+///         Default::default()
+///     }
+/// }
+///
+/// impl ProcessHandlers<AtomicU32> for Counter {
+///     fn register(registration: Registration<AtomicU32, Self>) -> Registration<AtomicU32, Self> {
+///         registration
+///             .task("Count 1", Self::count)
+///             .exclusive("equal to 3", |_, input| {
+///                 match input.load(Relaxed) {
+///                     3 => "YES",
+///                     _ => "NO",
+///                 }
+///                 .into()
+///             })
+///     }
+/// }
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bpmn = ProcessBuilder::new("examples/counter.bpmn")?
+///         .handlers(Counter { step: 1 })
+///         .build()?;
+///     let result = bpmn.run(AtomicU32::new(0))?;
+///     println!("{result:?}");
+///     Ok(())
+/// }
+/// ```
+pub trait ProcessHandlers<T>: Sized + Send + Sync + 'static {
+    /// Register the functions of the handlers.
+    fn register(registration: Registration<T, Self>) -> Registration<T, Self>;
+}
+
+/// Registers functions that take the shared handlers `H` on a [`ProcessBuilder`]. Created by
+/// [`ProcessBuilder::handlers`].
+pub struct Registration<T, H> {
+    builder: ProcessBuilder<T>,
+    handlers: Arc<H>,
+}
+
+impl<T, H> Registration<T, H>
+where
+    H: Send + Sync + 'static,
+{
+    // Bind the function to the shared handlers
+    fn bind<F, R>(&self, func: F) -> impl Fn(&T) -> R + 'static + Sync + Send
+    where
+        F: Fn(&H, &T) -> R + 'static + Sync + Send,
+    {
+        let handlers = Arc::clone(&self.handlers);
+        move |data| func(&handlers, data)
+    }
+
+    /// Register a task like [`ProcessBuilder::task`].
+    pub fn task<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&H, &T) -> Task + 'static + Sync + Send,
+    {
+        let func = self.bind(func);
+        self.builder = self.builder.task(name, func);
+        self
+    }
+
+    /// Register an exclusive gateway like [`ProcessBuilder::exclusive`].
+    pub fn exclusive<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&H, &T) -> Exclusive + 'static + Sync + Send,
+    {
+        let func = self.bind(func);
+        self.builder = self.builder.exclusive(name, func);
+        self
+    }
+
+    /// Register an exclusive gateway like [`ProcessBuilder::exclusive_choice`].
+    pub fn exclusive_choice<C, F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        C: FlowChoice,
+        F: Fn(&H, &T) -> C + 'static + Sync + Send,
+    {
+        let func = self.bind(func);
+        self.builder = self.builder.exclusive_choice(name, func);
+        self
+    }
+
+    /// Register an inclusive gateway like [`ProcessBuilder::inclusive`].
+    pub fn inclusive<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&H, &T) -> Inclusive + 'static + Sync + Send,
+    {
+        let func = self.bind(func);
+        self.builder = self.builder.inclusive(name, func);
+        self
+    }
+
+    /// Register an inclusive gateway like [`ProcessBuilder::inclusive_choice`].
+    pub fn inclusive_choice<C, F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        C: FlowChoice,
+        F: Fn(&H, &T) -> Vec<C> + 'static + Sync + Send,
+    {
+        let func = self.bind(func);
+        self.builder = self.builder.inclusive_choice(name, func);
+        self
+    }
+
+    /// Register an event-based gateway like [`ProcessBuilder::event_based`].
+    pub fn event_based<F>(mut self, name: impl Into<String>, func: F) -> Self
+    where
+        F: Fn(&H, &T) -> IntermediateEvent + 'static + Sync + Send,
+    {
+        let func = self.bind(func);
+        self.builder = self.builder.event_based(name, func);
+        self
+    }
+
+    /// Register anything else on the builder, such as external tasks or event callbacks, with access to the
+    /// shared handlers.
+    pub fn builder<F>(mut self, func: F) -> Self
+    where
+        F: FnOnce(ProcessBuilder<T>, &Arc<H>) -> ProcessBuilder<T>,
+    {
+        self.builder = func(self.builder, &self.handlers);
+        self
+    }
+}
+
+impl<T> ProcessBuilder<T> {
+    /// Register all functions of the [`ProcessHandlers`] in one call.
+    pub fn handlers<H>(self, handlers: H) -> Self
+    where
+        H: ProcessHandlers<T>,
+    {
+        H::register(Registration {
+            builder: self,
+            handlers: Arc::new(handlers),
+        })
+        .builder
+    }
+}
@@ -0,0 +1,662 @@
+use super::{
+    Process, RuntimeError,
+    controller::Controller,
+    handler::JobData,
+    inbox::{Inbox, InboxItem},
+    jobs::{Job, Jobs},
+    lock,
+    mailbox::{Mailbox, Payload},
+};
+use crate::{api::Task, bpmn::Activity};
+use log::{debug, warn};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    panic::{AssertUnwindSafe, catch_unwind},
+    sync::{
+        Arc, Condvar, Mutex, MutexGuard, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+/// Running process instance addressed by the id returned when it was started or by the
+/// business key given to `start_with_key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Correlation {
+    Instance(usize),
+    Key(String),
+}
+
+impl From<usize> for Correlation {
+    fn from(value: usize) -> Self {
+        Self::Instance(value)
+    }
+}
+
+impl From<&str> for Correlation {
+    fn from(value: &str) -> Self {
+        Self::Key(value.to_string())
+    }
+}
+
+impl From<String> for Correlation {
+    fn from(value: String) -> Self {
+        Self::Key(value)
+    }
+}
+
+/// Status of a process instance started by a [`Runtime`] or an [`Engine`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstanceStatus {
+    /// Tokens are making progress
+    Running,
+    /// Paused by its controller, see [`Engine::controller`]
+    Paused,
+    /// All tokens wait on timers, signals or messages
+    Waiting,
+    /// The instance has ended and the result can be taken
+    Completed,
+    /// The instance returned an error that can be taken as result
+    Failed,
+}
+
+/// Information about an instance returned by [`Engine::list`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct InstanceInfo {
+    pub id: usize,
+    /// Key the process was deployed under
+    pub process: String,
+    /// Business key given at start
+    pub key: Option<String>,
+    pub status: InstanceStatus,
+}
+
+// Result of an instance, set by its thread when it has ended.
+struct Finished<T> {
+    result: Mutex<Option<Result<T, RuntimeError>>>,
+    done: Condvar,
+}
+
+impl<T> Finished<T> {
+    fn lock(&self) -> MutexGuard<'_, Option<Result<T, RuntimeError>>> {
+        lock(&self.result)
+    }
+}
+
+struct Instance<T> {
+    process: String,
+    key: Option<String>,
+    mailbox: Arc<Mailbox<T>>,
+    controller: Controller,
+    finished: Arc<Finished<T>>,
+}
+
+impl<T> Instance<T> {
+    fn status(&self) -> InstanceStatus {
+        match self.finished.lock().as_ref() {
+            Some(Ok(_)) => InstanceStatus::Completed,
+            Some(Err(_)) => InstanceStatus::Failed,
+            None if self.controller.is_paused() => InstanceStatus::Paused,
+            None if self.mailbox.is_waiting() => InstanceStatus::Waiting,
+            None => InstanceStatus::Running,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.lock().is_some()
+    }
+}
+
+// Last generated id and the instances by id.
+type Entries<T> = (usize, BTreeMap<usize, Arc<Instance<T>>>);
+
+// Instances by id, shared by the runtime and the engine. An instance is kept until its result is taken.
+struct Instances<T> {
+    entries: Mutex<Entries<T>>,
+}
+
+impl<T> Default for Instances<T> {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+        }
+    }
+}
+
+impl<T> Instances<T>
+where
+    T: Send + Sync + 'static,
+{
+    fn lock(&self) -> MutexGuard<'_, Entries<T>> {
+        lock(&self.entries)
+    }
+
+    fn spawn(
+        &self,
+        process_key: &str,
+        process: Arc<Process<T>>,
+        key: Option<String>,
+        data: T,
+        work: Option<&Arc<Work>>,
+    ) -> usize {
+        let finished = Arc::new(Finished {
+            result: Mutex::new(None),
+            done: Condvar::new(),
+        });
+
+        let mut entries = self.lock();
+        entries.0 += 1;
+        let id = entries.0;
+        let mailbox = Arc::new(Mailbox::external(work.map(|work| (id, Arc::clone(work)))));
+        let controller = Controller::new();
+        debug!("start instance {id} of {process_key} {key:?}");
+        entries.1.insert(
+            id,
+            Arc::new(Instance {
+                process: process_key.to_string(),
+                key,
+                mailbox: Arc::clone(&mailbox),
+                controller: controller.clone(),
+                finished: Arc::clone(&finished),
+            }),
+        );
+
+        let work = work.map(Arc::clone);
+        std::thread::spawn(move || {
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                let start = process.diagram.main_process()?.start()?;
+                process
+                    .run_instance(start, data, &mailbox, Some(&controller))
+                    .map(|(data, _)| data)
+            }))
+            .unwrap_or_else(|_| Err(RuntimeError::Engine(format!("instance {id} panicked"))));
+
+            if let Err(error) = &result {
+                warn!("instance {id} failed: {error}");
+            }
+            mailbox.clear();
+            if let Some(work) = &work {
+                work.jobs.remove_instance(id);
+                work.inbox.remove_instance(id);
+            }
+            *finished.lock() = Some(result);
+            finished.done.notify_all();
+        });
+        id
+    }
+
+    fn correlate<R>(
+        &self,
+        correlation: Correlation,
+        deliver: impl FnOnce(&Instance<T>) -> R,
+    ) -> Result<R, RuntimeError> {
+        // Delivered without holding the lock, so other instances are not blocked
+        let instance = self
+            .lock()
+            .1
+            .iter()
+            .find(|(id, instance)| {
+                !instance.is_finished()
+                    && match &correlation {
+                        Correlation::Instance(instance_id) => *id == instance_id,
+                        Correlation::Key(key) => instance.key.as_ref() == Some(key),
+                    }
+            })
+            .map(|(_, instance)| Arc::clone(instance));
+        instance.map(|instance| deliver(&instance)).ok_or_else(|| {
+            RuntimeError::UnknownInstance(match correlation {
+                Correlation::Instance(id) => id.to_string(),
+                Correlation::Key(key) => key,
+            })
+        })
+    }
+
+    fn message(
+        &self,
+        instance_or_key: Correlation,
+        message_name: &str,
+        payload: impl FnOnce(&T) + Send + 'static,
+    ) -> Result<(), RuntimeError> {
+        self.correlate(instance_or_key, |instance| {
+            debug!("correlate {message_name} to {:?}", instance.key);
+            instance.mailbox.message(
+                message_name,
+                Box::new(|data| {
+                    payload(data);
+                    Task::Default
+                }),
+            );
+        })
+    }
+
+    fn status(&self, id: usize) -> Option<InstanceStatus> {
+        self.lock().1.get(&id).map(|instance| instance.status())
+    }
+
+    fn result(&self, id: usize) -> Option<Result<T, RuntimeError>> {
+        let mut entries = self.lock();
+        let result = entries.1.get(&id)?.finished.lock().take()?;
+        entries.1.remove(&id);
+        Some(result)
+    }
+
+    fn wait(&self, id: usize) -> Option<Result<T, RuntimeError>> {
+        let finished = Arc::clone(&self.lock().1.get(&id)?.finished);
+        let mut result = finished.lock();
+        while result.is_none() {
+            result = finished
+                .done
+                .wait(result)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        drop(result);
+        self.result(id)
+    }
+}
+
+// Jobs and user tasks published by the instances of an engine. Both share the id sequence.
+#[derive(Default)]
+pub(super) struct Work {
+    next_id: AtomicUsize,
+    jobs: Jobs,
+    inbox: Inbox,
+}
+
+impl Work {
+    fn next_id(&self) -> usize {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    pub(super) fn publish_job(
+        &self,
+        instance: usize,
+        topic: &str,
+        element_id: &str,
+        data: JobData,
+    ) -> usize {
+        let id = self.next_id();
+        self.jobs.publish(id, instance, topic, element_id, data);
+        id
+    }
+
+    // Remove the job or user task that is no longer waited on.
+    pub(super) fn withdraw(&self, id: usize) {
+        self.jobs.remove(id);
+        self.inbox.remove(id);
+    }
+
+    pub(super) fn publish_user_task(&self, instance: usize, activity: &Activity) -> usize {
+        let id = self.next_id();
+        self.inbox.publish(InboxItem {
+            id,
+            name: activity
+                .name
+                .clone()
+                .unwrap_or_else(|| activity.id.bpmn().to_string()),
+            element_id: activity.id.bpmn().to_string(),
+            documentation: activity.documentation.clone(),
+            lane: activity.lane.clone(),
+            assignee: activity.assignee.clone(),
+            instance,
+        });
+        id
+    }
+}
+
+/// Own running process instances and deliver messages to them.
+///
+/// In an instance started by the runtime, a token that reaches a receive task or a message
+/// intermediate catch event waits until a message with the same name is correlated to the instance.
+/// Messages that arrive before a token waits on them are kept until caught.
+/// Instances are run on their own thread. An ended instance and its result are kept until the
+/// result is taken with [`Runtime::wait`].
+///
+/// ```rust no_run
+/// use snurr::{ProcessBuilder, Runtime};
+/// use std::sync::{Arc, Mutex};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let process = ProcessBuilder::<Mutex<u32>>::new("payment.bpmn")?
+///         .task("Ship order", |_input| Default::default())
+///         .build()?;
+///
+///     let runtime = Runtime::new(Arc::new(process));
+///     let id = runtime.start_with_key("order-42", Default::default());
+///
+///     // The payload is applied to the process data when the message is caught
+///     runtime.correlate_message("order-42", "Payment received", |data| {
+///         *data.lock().unwrap() += 100;
+///     })?;
+///
+///     let result = runtime.wait(id).expect("instance exists")?;
+///     println!("{result:?}");
+///     Ok(())
+/// }
+/// ```
+pub struct Runtime<T> {
+    process: Arc<Process<T>>,
+    instances: Instances<T>,
+}
+
+impl<T> Runtime<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new(process: Arc<Process<T>>) -> Self {
+        Self {
+            process,
+            instances: Default::default(),
+        }
+    }
+
+    /// Start an instance from the start event of the main process and return its id.
+    pub fn start(&self, data: T) -> usize {
+        self.instances
+            .spawn("", Arc::clone(&self.process), None, data, None)
+    }
+
+    /// Start an instance that messages can be correlated to with the business `key`.
+    pub fn start_with_key(&self, key: impl Into<String>, data: T) -> usize {
+        self.instances
+            .spawn("", Arc::clone(&self.process), Some(key.into()), data, None)
+    }
+
+    /// Deliver the message to the instance. The first token waiting at a receive task or message
+    /// intermediate catch event named after `message_name` catches it, and `payload` is applied to
+    /// the process data before the token continues. If no token waits yet, the message is kept
+    /// until one does. Returns `RuntimeError::UnknownInstance` if no instance matched.
+    pub fn correlate_message(
+        &self,
+        instance_or_key: impl Into<Correlation>,
+        message_name: &str,
+        payload: impl FnOnce(&T) + Send + 'static,
+    ) -> Result<(), RuntimeError> {
+        self.instances
+            .message(instance_or_key.into(), message_name, payload)
+    }
+
+    /// Block until the instance has completed and return its result.
+    /// Returns None if the instance is unknown or its result has already been taken.
+    pub fn wait(&self, id: usize) -> Option<Result<T, RuntimeError>> {
+        self.instances.wait(id)
+    }
+}
+
+/// Deploy processes under a key and run many instances of them concurrently.
+///
+/// Each instance runs on its own thread and gets a generated id. Use the id, or the business key
+/// given at start, to query the status of the instance, to deliver messages and signals to it, and
+/// to take its result when it has ended. An ended instance and its result are kept until the result
+/// is taken with [`Engine::result`] or [`Engine::wait`].
+///
+/// ```rust no_run
+/// use snurr::{Engine, InstanceStatus, ProcessBuilder};
+/// use std::sync::Mutex;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let engine = Engine::new();
+///     engine.deploy(
+///         "order",
+///         ProcessBuilder::<Mutex<u32>>::new("order.bpmn")?
+///             .task("Ship order", |_input| Default::default())
+///             .build()?,
+///     );
+///
+///     let id = engine.start_with_key("order", "order-42", Default::default())?;
+///     engine.correlate_message("order-42", "Payment received", |data| {
+///         *data.lock().unwrap() += 100;
+///     })?;
+///
+///     if engine.status(id) == Some(InstanceStatus::Waiting) {
+///         engine.signal(id, "Cancel")?;
+///     }
+///     let result = engine.wait(id).expect("instance exists")?;
+///     println!("{result:?}");
+///     Ok(())
+/// }
+/// ```
+pub struct Engine<T> {
+    processes: Mutex<HashMap<String, Arc<Process<T>>>>,
+    instances: Instances<T>,
+    work: Arc<Work>,
+}
+
+impl<T> Default for Engine<T> {
+    fn default() -> Self {
+        Self {
+            processes: Default::default(),
+            instances: Default::default(),
+            work: Default::default(),
+        }
+    }
+}
+
+impl<T> Engine<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Deploy the process under `key`. A process already deployed under the key is replaced,
+    /// while its running instances continue.
+    pub fn deploy(&self, key: impl Into<String>, process: impl Into<Arc<Process<T>>>) {
+        lock(&self.processes).insert(key.into(), process.into());
+    }
+
+    /// Keys of the deployed processes.
+    pub fn deployed(&self) -> Vec<String> {
+        let mut keys: Vec<_> = lock(&self.processes).keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Start an instance of the process deployed under `process` and return its id.
+    /// Returns `RuntimeError::UnknownProcess` if no process is deployed under the key.
+    pub fn start(&self, process: &str, data: T) -> Result<usize, RuntimeError> {
+        Ok(self.instances.spawn(
+            process,
+            self.process(process)?,
+            None,
+            data,
+            Some(&self.work),
+        ))
+    }
+
+    /// Start an instance that messages and signals can be routed to with the business `key`.
+    pub fn start_with_key(
+        &self,
+        process: &str,
+        key: impl Into<String>,
+        data: T,
+    ) -> Result<usize, RuntimeError> {
+        Ok(self.instances.spawn(
+            process,
+            self.process(process)?,
+            Some(key.into()),
+            data,
+            Some(&self.work),
+        ))
+    }
+
+    fn process(&self, key: &str) -> Result<Arc<Process<T>>, RuntimeError> {
+        lock(&self.processes)
+            .get(key)
+            .cloned()
+            .ok_or_else(|| RuntimeError::UnknownProcess(key.to_string()))
+    }
+
+    /// Status of the instance, or None if it is unknown or its result has been taken.
+    pub fn status(&self, id: usize) -> Option<InstanceStatus> {
+        self.instances.status(id)
+    }
+
+    /// Controller to pause and resume the instance and to query the position of its tokens.
+    /// Returns None if the instance is unknown or has ended.
+    pub fn controller(&self, id: usize) -> Option<Controller> {
+        self.instances
+            .lock()
+            .1
+            .get(&id)
+            .filter(|instance| !instance.is_finished())
+            .map(|instance| instance.controller.clone())
+    }
+
+    /// All instances whose result has not been taken, ordered by id.
+    pub fn list(&self) -> Vec<InstanceInfo> {
+        self.instances
+            .lock()
+            .1
+            .iter()
+            .map(|(id, instance)| InstanceInfo {
+                id: *id,
+                process: instance.process.clone(),
+                key: instance.key.clone(),
+                status: instance.status(),
+            })
+            .collect()
+    }
+
+    /// Deliver the message to the instance, see [`Runtime::correlate_message`].
+    pub fn correlate_message(
+        &self,
+        instance_or_key: impl Into<Correlation>,
+        message_name: &str,
+        payload: impl FnOnce(&T) + Send + 'static,
+    ) -> Result<(), RuntimeError> {
+        self.instances
+            .message(instance_or_key.into(), message_name, payload)
+    }
+
+    /// Throw the signal in the instance. It wakes every token waiting on a signal catch event or
+    /// signal boundary with the same name. Returns `RuntimeError::UnknownInstance` if no instance
+    /// matched.
+    pub fn signal(
+        &self,
+        instance_or_key: impl Into<Correlation>,
+        signal_name: &str,
+    ) -> Result<(), RuntimeError> {
+        self.instances
+            .correlate(instance_or_key.into(), |instance| {
+                instance.mailbox.signal(signal_name)
+            })
+    }
+
+    /// Throw the signal in every instance that has not ended and return the number of instances.
+    pub fn broadcast_signal(&self, signal_name: &str) -> usize {
+        let instances: Vec<_> = self
+            .instances
+            .lock()
+            .1
+            .values()
+            .filter(|instance| !instance.is_finished())
+            .cloned()
+            .collect();
+        instances
+            .iter()
+            .for_each(|instance| instance.mailbox.signal(signal_name));
+        instances.len()
+    }
+
+    /// Lock at most `max` jobs published on the topic to the worker, oldest first.
+    /// Locked jobs are not fetched again until the worker completes or fails them.
+    pub fn fetch_and_lock(&self, topic: &str, worker: &str, max: usize) -> Vec<Job> {
+        self.work.jobs.fetch_and_lock(topic, worker, max)
+    }
+
+    /// Complete the job locked by the worker. `payload` is applied to the process data and the
+    /// instance continues after the external task.
+    /// Returns `RuntimeError::UnknownJob` if the job is not locked by the worker.
+    pub fn complete_job(
+        &self,
+        job: usize,
+        worker: &str,
+        payload: impl FnOnce(&T) + Send + 'static,
+    ) -> Result<(), RuntimeError> {
+        self.finish_job(
+            job,
+            worker,
+            Box::new(|data| {
+                payload(data);
+                Task::Default
+            }),
+        )
+    }
+
+    /// Fail the job locked by the worker. The instance follows the error boundary of the external task
+    /// matching the error code, see `Task::error_code`.
+    /// Returns `RuntimeError::UnknownJob` if the job is not locked by the worker.
+    pub fn fail_job(
+        &self,
+        job: usize,
+        worker: &str,
+        error_code: impl Into<Cow<'static, str>>,
+    ) -> Result<(), RuntimeError> {
+        let code = error_code.into();
+        self.finish_job(job, worker, Box::new(|_| Task::error_code(code)))
+    }
+
+    fn finish_job(
+        &self,
+        job: usize,
+        worker: &str,
+        payload: Payload<T>,
+    ) -> Result<(), RuntimeError> {
+        let Job { id, instance, .. } = self.work.jobs.take_locked(job, worker)?;
+        debug!("worker {worker} finished job {id}");
+        self.instances
+            .correlate(Correlation::Instance(instance), |instance| {
+                instance.mailbox.complete(id, payload)
+            })?
+            .then_some(())
+            .ok_or(RuntimeError::UnknownJob(id))
+    }
+
+    /// User tasks waiting to be completed, ordered by id.
+    pub fn inbox(&self) -> Vec<InboxItem> {
+        self.work.inbox.list()
+    }
+
+    /// Assign the user task to the user. Returns `RuntimeError::NotAssigned` if it is assigned to another user.
+    pub fn claim(&self, item: usize, user: &str) -> Result<(), RuntimeError> {
+        self.work.inbox.claim(item, user)
+    }
+
+    /// Assign the user task to another user, or unassign it with None.
+    pub fn reassign(&self, item: usize, user: Option<&str>) -> Result<(), RuntimeError> {
+        self.work.inbox.reassign(item, user)
+    }
+
+    /// Complete the user task assigned to the user. `outcome` updates the process data and returns
+    /// the result of the task, that continues the instance after the task or selects a boundary.
+    /// Returns `RuntimeError::NotAssigned` if the task is not assigned to the user, and
+    /// `RuntimeError::UnknownUserTask` if it has already been completed.
+    pub fn complete_user_task(
+        &self,
+        item: usize,
+        user: &str,
+        outcome: impl FnOnce(&T) -> Task + Send + 'static,
+    ) -> Result<(), RuntimeError> {
+        let InboxItem { id, instance, .. } = self.work.inbox.take_assigned(item, user)?;
+        debug!("{user} completed user task {id}");
+        self.instances
+            .correlate(Correlation::Instance(instance), |instance| {
+                instance.mailbox.complete(id, Box::new(outcome))
+            })?
+            .then_some(())
+            .ok_or(RuntimeError::UnknownUserTask(id))
+    }
+
+    /// Take the result of the instance if it has ended. The instance is removed.
+    pub fn result(&self, id: usize) -> Option<Result<T, RuntimeError>> {
+        self.instances.result(id)
+    }
+
+    /// Block until the instance has ended and take its result. The instance is removed.
+    /// Returns None if the instance is unknown or its result has already been taken.
+    pub fn wait(&self, id: usize) -> Option<Result<T, RuntimeError>> {
+        self.instances.wait(id)
+    }
+}
@@ -0,0 +1,287 @@
+use super::{Process, RuntimeError, lock};
+use crate::{
+    bpmn::Event,
+    clock::{Clock, WakeId},
+    timer::Timer,
+};
+use log::{debug, warn};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// Information about a schedule created by [`Scheduler::schedule`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Schedule {
+    /// Used to stop the schedule
+    pub id: usize,
+    /// Name or id of the timer start event
+    pub start_event: String,
+    /// When the next process instance is started
+    pub next: SystemTime,
+    /// Number of process instances started so far
+    pub started: u32,
+}
+
+#[derive(Default)]
+struct Schedules {
+    next_id: usize,
+    entries: BTreeMap<usize, Schedule>,
+    // Pending wake up of each schedule, cancelled when it is stopped
+    wakes: HashMap<usize, (Arc<dyn Clock>, WakeId)>,
+}
+
+// Progress of arming a job. The clock can call the wake up before `wake_at` returns.
+enum Arming {
+    Waiting,
+    Due,
+    Armed,
+}
+
+/// Start process instances from timer start events.
+///
+/// Each start event with a timer definition in the process gets a schedule. A `timeDate` or a
+/// `timeDuration` starts one instance, while a `timeCycle` starts an instance for every repetition.
+/// Repetitions of a cycle with a start in the past have elapsed and are not started.
+/// Instances are run on their own thread and time is taken from the clock registered on the process.
+///
+/// ```rust no_run
+/// use snurr::{ProcessBuilder, Scheduler};
+/// use std::sync::Arc;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let process = ProcessBuilder::<()>::new("timer_start.bpmn")?
+///         .task("Send report", |_input| Default::default())
+///         .build()?;
+///
+///     let scheduler = Scheduler::new();
+///     let ids = scheduler.schedule(
+///         Arc::new(process),
+///         || (),
+///         |result| println!("instance completed: {result:?}"),
+///     );
+///
+///     for schedule in scheduler.list() {
+///         println!("{} starts next at {:?}", schedule.start_event, schedule.next);
+///     }
+///
+///     // Stop the schedules when no more instances should start
+///     ids.into_iter().for_each(|id| {
+///         scheduler.stop(id);
+///     });
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct Scheduler {
+    schedules: Arc<Mutex<Schedules>>,
+}
+
+// Everything needed to start instances from one timer start event
+struct Job<T> {
+    id: usize,
+    start: usize,
+    timer: Timer,
+    // Repetitions of the cycle that elapsed before it was scheduled
+    elapsed: u32,
+    process: Arc<Process<T>>,
+    data: Arc<dyn Fn() -> T + Send + Sync>,
+    on_result: Arc<dyn Fn(Result<T, RuntimeError>) + Send + Sync>,
+    schedules: Arc<Mutex<Schedules>>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a schedule for every timer start event in the process and return their ids.
+    /// `data` creates the input for each instance as it starts, and `on_result` receives the result
+    /// when the instance has completed.
+    pub fn schedule<T>(
+        &self,
+        process: Arc<Process<T>>,
+        data: impl Fn() -> T + Send + Sync + 'static,
+        on_result: impl Fn(Result<T, RuntimeError>) + Send + Sync + 'static,
+    ) -> Vec<usize>
+    where
+        T: Send + Sync + 'static,
+    {
+        let starts: Vec<(usize, String, Timer)> = match process.diagram.main_process() {
+            Ok(main) => main
+                .timer_starts()
+                .filter_map(
+                    |(
+                        index,
+                        Event {
+                            id, name, timer, ..
+                        },
+                    )| {
+                        let start_event = name.as_deref().unwrap_or(id.bpmn()).to_string();
+                        Some((index, start_event, timer.clone()?))
+                    },
+                )
+                .collect(),
+            Err(error) => {
+                warn!("could not schedule process: {error}");
+                return vec![];
+            }
+        };
+
+        let data: Arc<dyn Fn() -> T + Send + Sync> = Arc::new(data);
+        let on_result: Arc<dyn Fn(Result<T, RuntimeError>) + Send + Sync> = Arc::new(on_result);
+        let now = process.clock.now();
+        let mut ids = vec![];
+        for (start, start_event, timer) in starts {
+            let Some(next) = timer.deadline(now) else {
+                debug!("{start_event} has no repetitions left");
+                continue;
+            };
+            let id = {
+                let mut schedules = lock(&self.schedules);
+                schedules.next_id += 1;
+                let id = schedules.next_id;
+                schedules.entries.insert(
+                    id,
+                    Schedule {
+                        id,
+                        start_event,
+                        next,
+                        started: 0,
+                    },
+                );
+                id
+            };
+
+            Job::arm(
+                Arc::new(Job {
+                    id,
+                    start,
+                    elapsed: timer.elapsed(now),
+                    timer,
+                    process: Arc::clone(&process),
+                    data: Arc::clone(&data),
+                    on_result: Arc::clone(&on_result),
+                    schedules: Arc::clone(&self.schedules),
+                }),
+                next,
+            );
+            ids.push(id);
+        }
+        ids
+    }
+
+    /// Active schedules. A schedule is removed when it has been stopped or when it will not start more instances.
+    pub fn list(&self) -> Vec<Schedule> {
+        lock(&self.schedules).entries.values().cloned().collect()
+    }
+
+    /// Stop a schedule. Instances that have already started run to completion. Returns false if the schedule was not active.
+    pub fn stop(&self, id: usize) -> bool {
+        let (active, wake) = {
+            let mut schedules = lock(&self.schedules);
+            (
+                schedules.entries.remove(&id).is_some(),
+                schedules.wakes.remove(&id),
+            )
+        };
+        if let Some((clock, wake)) = wake {
+            clock.cancel(wake);
+        }
+        active
+    }
+
+    /// Stop all schedules.
+    pub fn stop_all(&self) {
+        let wakes = {
+            let mut schedules = lock(&self.schedules);
+            schedules.entries.clear();
+            std::mem::take(&mut schedules.wakes)
+        };
+        wakes
+            .into_values()
+            .for_each(|(clock, wake)| clock.cancel(wake));
+    }
+}
+
+impl<T> Job<T>
+where
+    T: Send + Sync + 'static,
+{
+    // Start an instance when the clock reaches the deadline. Deadlines the clock has already reached
+    // are started in a loop, as a clock may wake before `wake_at` returns.
+    fn arm(job: Arc<Self>, mut deadline: SystemTime) {
+        let clock = Arc::clone(&job.process.clock);
+        loop {
+            let arming = Arc::new(Mutex::new(Arming::Waiting));
+            let wake = {
+                let (job, arming) = (Arc::clone(&job), Arc::clone(&arming));
+                clock.wake_at(
+                    deadline,
+                    Box::new(move || {
+                        let mut arming = lock(&arming);
+                        if let Arming::Waiting = *arming {
+                            *arming = Arming::Due;
+                        } else {
+                            drop(arming);
+                            if let Some(next) = job.fire(deadline) {
+                                Self::arm(job, next);
+                            }
+                        }
+                    }),
+                )
+            };
+
+            let mut arming = lock(&arming);
+            if let Arming::Due = *arming {
+                drop(arming);
+                match job.fire(deadline) {
+                    Some(next) => deadline = next,
+                    None => return,
+                }
+            } else {
+                *arming = Arming::Armed;
+                let mut schedules = lock(&job.schedules);
+                if schedules.entries.contains_key(&job.id) {
+                    schedules.wakes.insert(job.id, (Arc::clone(&clock), wake));
+                } else {
+                    // Stopped while arming
+                    drop(schedules);
+                    clock.cancel(wake);
+                }
+                return;
+            }
+        }
+    }
+
+    // Start an instance and return the next deadline, if the schedule continues.
+    fn fire(self: &Arc<Self>, deadline: SystemTime) -> Option<SystemTime> {
+        let next = {
+            let mut schedules = lock(&self.schedules);
+            schedules.wakes.remove(&self.id);
+            // None if stopped
+            let schedule = schedules.entries.get_mut(&self.id)?;
+            schedule.started += 1;
+            let next = self.timer.next(deadline, self.elapsed + schedule.started);
+            match next {
+                Some(next) => schedule.next = next,
+                None => {
+                    schedules.entries.remove(&self.id);
+                }
+            }
+            next
+        };
+
+        debug!("schedule {} starts an instance", self.id);
+        // Created before the instance is started, so stopping the schedule from `data` takes effect at once
+        let data = (self.data)();
+        let job = Arc::clone(self);
+        std::thread::spawn(move || {
+            let result = job.process.run_at(job.start, data).map(|(data, _)| data);
+            (job.on_result)(result);
+        });
+        next
+    }
+}
@@ -0,0 +1,219 @@
+use super::{ProcessBuilder, handler::Scopes, lock};
+use crate::bpmn::Mapping;
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// Value of a process variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+macro_rules! value {
+    ($variant:ident, $ty:ty $(, $from:ty)*) => {
+        impl From<$ty> for Value {
+            fn from(value: $ty) -> Self {
+                Value::$variant(value)
+            }
+        }
+
+        $(
+            impl From<$from> for Value {
+                fn from(value: $from) -> Self {
+                    Value::$variant(value.into())
+                }
+            }
+        )*
+
+        impl TryFrom<Value> for $ty {
+            type Error = Value;
+
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::$variant(value) => Ok(value),
+                    other => Err(other),
+                }
+            }
+        }
+    };
+}
+
+value!(Bool, bool);
+value!(Int, i64, i32, u32);
+value!(Float, f64, f32);
+value!(String, String, &str);
+
+#[derive(Debug, Default)]
+struct Scope {
+    values: Mutex<BTreeMap<String, Value>>,
+    parent: Option<Arc<Scope>>,
+}
+
+impl Scope {
+    fn values(&self) -> MutexGuard<'_, BTreeMap<String, Value>> {
+        lock(&self.values)
+    }
+
+    fn scopes(self: &Arc<Self>) -> impl Iterator<Item = &Arc<Scope>> {
+        std::iter::successors(Some(self), |scope| scope.parent.as_ref())
+    }
+}
+
+/// Named process variables, used as process data.
+///
+/// The process has one scope and each subprocess a scope of its own that sees the variables of the
+/// enclosing scopes. Tasks and subprocesses with input or output mappings in the diagram only see
+/// the variables mapped to them. Register the scopes with [`ProcessBuilder::variable_scopes`].
+///
+/// ```rust no_run
+/// use snurr::{ProcessBuilder, Variables};
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bpmn = ProcessBuilder::<Variables>::new("examples/counter.bpmn")?
+///         .variable_scopes()
+///         .task("Count 1", |input| {
+///             let count = input.get::<i64>("count").unwrap_or_default();
+///             input.set("count", count + 1);
+///             Default::default()
+///         })
+///         .exclusive("equal to 3", |input| {
+///             match input.get::<i64>("count") {
+///                 Some(3) => "YES",
+///                 _ => "NO",
+///             }
+///             .into()
+///         })
+///         .build()?;
+///
+///     let result = bpmn.run(Variables::new().with("count", 0))?;
+///     println!("{:?}", result.get::<i64>("count"));
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Variables {
+    scope: Arc<Scope>,
+}
+
+impl Variables {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a variable in the process scope and return the variables.
+    pub fn with(self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    /// Value of the variable in the nearest scope, converted to `V`. None if the variable is missing or
+    /// has another type.
+    pub fn get<V>(&self, name: &str) -> Option<V>
+    where
+        V: TryFrom<Value>,
+    {
+        self.value(name)?.try_into().ok()
+    }
+
+    /// Value of the variable in the nearest scope.
+    pub fn value(&self, name: &str) -> Option<Value> {
+        self.scope
+            .scopes()
+            .find_map(|scope| scope.values().get(name).cloned())
+    }
+
+    /// Update the variable in the nearest scope that has it, otherwise create it in the outermost scope.
+    pub fn set(&self, name: impl Into<String>, value: impl Into<Value>) {
+        let name = name.into();
+        let scope = self
+            .scope
+            .scopes()
+            .find(|scope| scope.values().contains_key(&name))
+            .or_else(|| self.scope.scopes().last())
+            .unwrap_or(&self.scope);
+        scope.values().insert(name, value.into());
+    }
+
+    /// Set the variable in the current scope. It is removed when the subprocess completes.
+    pub fn set_local(&self, name: impl Into<String>, value: impl Into<Value>) {
+        self.scope.values().insert(name.into(), value.into());
+    }
+
+    /// Remove the variable from the nearest scope that has it and return its value.
+    pub fn remove(&self, name: &str) -> Option<Value> {
+        self.scope
+            .scopes()
+            .find_map(|scope| scope.values().remove(name))
+    }
+
+    /// All variables visible in the current scope.
+    pub fn to_map(&self) -> BTreeMap<String, Value> {
+        let mut map = BTreeMap::new();
+        for scope in self.scope.scopes() {
+            for (name, value) in scope.values().iter() {
+                map.entry(name.clone()).or_insert_with(|| value.clone());
+            }
+        }
+        map
+    }
+
+    // Scope of a subprocess or of an activity with a mapping. Mapped activities only see their inputs.
+    fn enter(&self, mapping: Option<&Mapping>) -> Self {
+        let Some(mapping) = mapping else {
+            return Self {
+                scope: Arc::new(Scope {
+                    values: Default::default(),
+                    parent: Some(Arc::clone(&self.scope)),
+                }),
+            };
+        };
+        let scoped = Self::new();
+        for (source, target) in &mapping.inputs {
+            if let Some(value) = self.value(source) {
+                scoped.set_local(target, value);
+            }
+        }
+        scoped
+    }
+
+    fn leave(&self, scoped: &Self, mapping: &Mapping) {
+        for (source, target) in &mapping.outputs {
+            if let Some(value) = scoped.value(source) {
+                self.set(target, value);
+            }
+        }
+    }
+}
+
+impl ProcessBuilder<Variables> {
+    /// Give each subprocess a scope of its own and apply the input and output mappings of the diagram.
+    ///
+    /// Mappings are read from data input and data output associations, where the name of the data object
+    /// or data store is the name of the variable, and from `input` and `output` mappings with a `source`
+    /// and a `target` in the extension elements. A source is the name of a variable, expressions are not
+    /// evaluated. The mapped outputs are copied to the enclosing scope when the activity completes.
+    pub fn variable_scopes(mut self) -> Self {
+        self.handler.set_scopes(Scopes {
+            enter: Box::new(|data: &Variables, mapping| data.enter(mapping)),
+            leave: Box::new(|data: &Variables, scoped, mapping| data.leave(scoped, mapping)),
+        });
+        self
+    }
+}
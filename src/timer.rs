@@ -0,0 +1,413 @@
+use crate::{
+    bpmn::{TIME_CYCLE, TIME_DATE, TIME_DURATION},
+    diagram::reader::BpmnError,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_MINUTE: f64 = 60.0;
+const SECONDS_PER_HOUR: f64 = 60.0 * SECONDS_PER_MINUTE;
+const SECONDS_PER_DAY: f64 = 24.0 * SECONDS_PER_HOUR;
+const SECONDS_PER_WEEK: f64 = 7.0 * SECONDS_PER_DAY;
+// Calendar units are approximated, a month is 30 days and a year is 365 days.
+const SECONDS_PER_MONTH: f64 = 30.0 * SECONDS_PER_DAY;
+const SECONDS_PER_YEAR: f64 = 365.0 * SECONDS_PER_DAY;
+// Longest duration accepted. With four digit years, adding it to a date never overflows the system time.
+const MAX_SECONDS: f64 = 10_000.0 * SECONDS_PER_YEAR;
+
+// Timer definition from a timer event definition
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Timer {
+    // timeDuration, such as `PT5M`
+    Duration(Duration),
+    // timeDate, such as `2026-10-18T12:00:00Z`
+    Date(SystemTime),
+    // timeCycle, such as `R3/PT1H` or `R/2026-10-18T12:00:00Z/P1D`. No repetitions is unbounded.
+    Cycle {
+        repetitions: Option<u32>,
+        start: Option<SystemTime>,
+        interval: Duration,
+    },
+}
+
+impl Timer {
+    pub(crate) fn parse(bpmn_type: &str, value: &str) -> Result<Self, BpmnError> {
+        Ok(match bpmn_type {
+            TIME_DURATION => Timer::Duration(parse_duration(value)?),
+            TIME_DATE => Timer::Date(parse_date(value)?),
+            TIME_CYCLE => parse_cycle(value)?,
+            _ => Err(BpmnError::TypeNotImplemented(bpmn_type.into()))?,
+        })
+    }
+
+    // The first point in time the timer fires, if started at `now`. None if all repetitions of a
+    // cycle have already elapsed.
+    pub(crate) fn deadline(&self, now: SystemTime) -> Option<SystemTime> {
+        match self {
+            Timer::Duration(duration) => Some(now + *duration),
+            Timer::Date(date) => Some(*date),
+            Timer::Cycle {
+                repetitions,
+                start: Some(start),
+                interval,
+            } => {
+                let elapsed = self.elapsed(now);
+                if repetitions.is_some_and(|repetitions| elapsed >= repetitions) {
+                    return None;
+                }
+                Some(*start + interval.saturating_mul(elapsed))
+            }
+            Timer::Cycle { interval, .. } => Some(now + *interval),
+        }
+    }
+
+    // Repetitions of a cycle that have elapsed before `now` because its start is in the past.
+    pub(crate) fn elapsed(&self, now: SystemTime) -> u32 {
+        match self {
+            Timer::Cycle {
+                start: Some(start),
+                interval,
+                ..
+            } if *start < now && !interval.is_zero() => {
+                let elapsed = now.duration_since(*start).unwrap_or_default();
+                let cycles = elapsed.as_nanos().div_ceil(interval.as_nanos());
+                cycles.try_into().unwrap_or(u32::MAX)
+            }
+            _ => 0,
+        }
+    }
+
+    // The point in time after `previous` when the timer fires again, if it has fired `fired` times.
+    pub(crate) fn next(&self, previous: SystemTime, fired: u32) -> Option<SystemTime> {
+        match self {
+            Timer::Cycle {
+                repetitions,
+                interval,
+                ..
+            } if repetitions.is_none_or(|repetitions| fired < repetitions) => {
+                previous.checked_add(*interval)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Parse an ISO-8601 duration such as `PT5S`, `PT1H30M`, `P1DT12H` or `PT0.5S`.
+pub(crate) fn parse_duration(value: &str) -> Result<Duration, BpmnError> {
+    let invalid = || BpmnError::InvalidTimer(value.into());
+    let rest = value.trim().strip_prefix('P').ok_or_else(invalid)?;
+    if rest.is_empty() {
+        Err(invalid())?
+    }
+
+    let mut seconds = 0.0;
+    let mut in_time = false;
+    let mut number = String::new();
+    for c in rest.chars() {
+        let unit = match (c, in_time) {
+            ('T', false) if number.is_empty() => {
+                in_time = true;
+                continue;
+            }
+            ('0'..='9' | '.' | ',', _) => {
+                number.push(if c == ',' { '.' } else { c });
+                continue;
+            }
+            ('Y', false) => SECONDS_PER_YEAR,
+            ('M', false) => SECONDS_PER_MONTH,
+            ('W', false) => SECONDS_PER_WEEK,
+            ('D', false) => SECONDS_PER_DAY,
+            ('H', true) => SECONDS_PER_HOUR,
+            ('M', true) => SECONDS_PER_MINUTE,
+            ('S', true) => 1.0,
+            _ => Err(invalid())?,
+        };
+        seconds += number.parse::<f64>().map_err(|_| invalid())? * unit;
+        number.clear();
+    }
+
+    // Trailing number without unit or a `T` without any time component.
+    if !number.is_empty() || rest.ends_with('T') || seconds > MAX_SECONDS {
+        Err(invalid())?
+    }
+    Duration::try_from_secs_f64(seconds).map_err(|_| invalid())
+}
+
+// Parse an ISO-8601 date time such as `2026-10-18T12:00:00Z` or `2026-10-18T14:00:00.5+02:00`.
+// A date time without an offset is treated as UTC.
+pub(crate) fn parse_date(value: &str) -> Result<SystemTime, BpmnError> {
+    let invalid = || BpmnError::InvalidTimer(value.into());
+    let value = value.trim();
+    let (date, time) = value.split_once('T').ok_or_else(invalid)?;
+
+    let number = |s: &str| {
+        // Signs are only allowed in the offset
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            Err(invalid())?
+        }
+        s.parse::<i64>().map_err(|_| invalid())
+    };
+    let [year, month, day] = match date.split('-').collect::<Vec<_>>().as_slice() {
+        [year, month, day] if year.len() == 4 && month.len() == 2 && day.len() == 2 => {
+            [number(year)?, number(month)?, number(day)?]
+        }
+        _ => Err(invalid())?,
+    };
+
+    // Split time and offset
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(index) => time.split_at(index),
+        None => (time, ""),
+    };
+    let offset = match offset.as_bytes() {
+        [] | [b'Z'] => 0,
+        [sign @ (b'+' | b'-'), ..] => {
+            let offset = &offset[1..];
+            let (hours, minutes) = offset
+                .split_once(':')
+                .or_else(|| offset.is_char_boundary(2).then(|| offset.split_at(2)))
+                .ok_or_else(invalid)?;
+            let seconds = number(hours)? * 3600
+                + if minutes.is_empty() {
+                    0
+                } else {
+                    number(minutes)? * 60
+                };
+            if *sign == b'-' { -seconds } else { seconds }
+        }
+        _ => Err(invalid())?,
+    };
+
+    let (hour, minute, second) = match time.split(':').collect::<Vec<_>>().as_slice() {
+        [hour, minute] => (number(hour)?, number(minute)?, 0.0),
+        [hour, minute, second] => (
+            number(hour)?,
+            number(minute)?,
+            second
+                .starts_with(|c: char| c.is_ascii_digit())
+                .then(|| second.replace(',', ".").parse::<f64>().ok())
+                .flatten()
+                .ok_or_else(invalid)?,
+        ),
+        _ => Err(invalid())?,
+    };
+
+    if !(1..=12).contains(&month)
+        || !(1..=days_in_month(year, month)).contains(&day)
+        || !(0..=23).contains(&hour)
+        || !(0..=59).contains(&minute)
+        || !(0.0..60.0).contains(&second)
+    {
+        Err(invalid())?
+    }
+
+    let seconds = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 - offset;
+    let fraction = Duration::try_from_secs_f64(second).map_err(|_| invalid())?;
+    let time = if seconds >= 0 {
+        UNIX_EPOCH.checked_add(Duration::from_secs(seconds.unsigned_abs()))
+    } else {
+        UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+    };
+    time.and_then(|time| time.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+// Parse an ISO-8601 repeating interval such as `R5/PT10S`, `R/PT1H` or `R3/2026-10-18T12:00:00Z/PT1H`.
+pub(crate) fn parse_cycle(value: &str) -> Result<Timer, BpmnError> {
+    let invalid = || BpmnError::InvalidTimer(value.into());
+    let mut parts = value.trim().split('/');
+    let repetitions = match parts.next().and_then(|s| s.strip_prefix('R')) {
+        Some("") => None,
+        Some(n) if n.bytes().all(|b| b.is_ascii_digit()) => {
+            Some(n.parse::<u32>().map_err(|_| invalid())?)
+        }
+        Some(_) => Err(invalid())?,
+        None => Err(invalid())?,
+    };
+    let (start, interval) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(interval), None, None) => (None, parse_duration(interval)?),
+        (Some(start), Some(interval), None) => {
+            (Some(parse_date(start)?), parse_duration(interval)?)
+        }
+        _ => Err(invalid())?,
+    };
+    if interval.is_zero() {
+        Err(invalid())?
+    }
+    Ok(Timer::Cycle {
+        repetitions,
+        start,
+        interval,
+    })
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+// See: https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(parse_duration("PT5S")?, Duration::from_secs(5));
+        assert_eq!(parse_duration("PT1H30M")?, Duration::from_secs(5400));
+        assert_eq!(parse_duration("P1DT12H")?, Duration::from_secs(129_600));
+        assert_eq!(parse_duration("P2W")?, Duration::from_secs(1_209_600));
+        assert_eq!(parse_duration("PT0.5S")?, Duration::from_millis(500));
+        assert_eq!(parse_duration(" PT0,25S ")?, Duration::from_millis(250));
+        for invalid in [
+            "",
+            "P",
+            "PT",
+            "5S",
+            "PT5",
+            "P5H",
+            "PTS",
+            "P1DT",
+            "P300000000000Y",
+        ] {
+            assert!(parse_duration(invalid).is_err(), "{invalid} should fail");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn longest_timer() -> Result<(), Box<dyn std::error::Error>> {
+        let longest = parse_duration("P10000Y")?;
+        let last = parse_date("9999-12-31T23:59:59Z")?;
+        assert_eq!(
+            Timer::Duration(longest).deadline(last),
+            last.checked_add(longest)
+        );
+        let cycle = parse_cycle("R/9999-12-31T23:59:59Z/P10000Y")?;
+        assert!(cycle.next(last, 1).is_some());
+        assert!(parse_duration("P10001Y").is_err());
+        assert!(parse_date("10000-01-01T00:00:00Z").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn dates() -> Result<(), Box<dyn std::error::Error>> {
+        let at = |secs| UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(parse_date("1970-01-01T00:00:00Z")?, at(0));
+        assert_eq!(parse_date("2026-10-18T12:00:00Z")?, at(1_792_324_800));
+        assert_eq!(parse_date("2026-10-18T12:00:00")?, at(1_792_324_800));
+        assert_eq!(parse_date("2026-10-18T14:00:00+02:00")?, at(1_792_324_800));
+        assert_eq!(parse_date("2026-10-18T07:30:00-0430")?, at(1_792_324_800));
+        assert_eq!(parse_date("2026-10-18T12:00")?, at(1_792_324_800));
+        assert_eq!(parse_date("2024-02-29T00:00:00Z")?, at(1_709_164_800));
+        assert_eq!(parse_date("2000-02-29T00:00:00Z")?, at(951_782_400));
+        assert_eq!(
+            parse_date("2026-10-18T12:00:00.5Z")?,
+            at(1_792_324_800) + Duration::from_millis(500)
+        );
+        for invalid in [
+            "2026-10-18",
+            "2026-13-01T00:00:00Z",
+            "2026-10-18T25:00:00Z",
+            "2026-02-29T00:00:00Z",
+            "2100-02-29T00:00:00Z",
+            "2026-04-31T00:00:00Z",
+            "+2026-10-18T12:00:00Z",
+            "2026-10-18T+1:00:00Z",
+            "2026-10-18T12:00:+0Z",
+            "tomorrow",
+        ] {
+            assert!(parse_date(invalid).is_err(), "{invalid} should fail");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn cycles() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(
+            parse_cycle("R/PT1H")?,
+            Timer::Cycle {
+                repetitions: None,
+                start: None,
+                interval: Duration::from_secs(3600)
+            }
+        );
+        assert_eq!(
+            parse_cycle("R3/2026-10-18T12:00:00Z/PT10S")?,
+            Timer::Cycle {
+                repetitions: Some(3),
+                start: Some(UNIX_EPOCH + Duration::from_secs(1_792_324_800)),
+                interval: Duration::from_secs(10)
+            }
+        );
+        for invalid in ["PT1H", "R/", "Rx/PT1H", "R+3/PT1H", "R/PT0S", "0 0 * * * ?"] {
+            assert!(parse_cycle(invalid).is_err(), "{invalid} should fail");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn deadlines() {
+        let now = UNIX_EPOCH + Duration::from_secs(1000);
+        let cycle = |start| Timer::Cycle {
+            repetitions: None,
+            start,
+            interval: Duration::from_secs(60),
+        };
+        assert_eq!(
+            Timer::Duration(Duration::from_secs(5)).deadline(now),
+            Some(now + Duration::from_secs(5))
+        );
+        assert_eq!(
+            cycle(None).deadline(now),
+            Some(now + Duration::from_secs(60))
+        );
+        assert_eq!(
+            cycle(Some(UNIX_EPOCH)).deadline(now),
+            Some(UNIX_EPOCH + Duration::from_secs(1020))
+        );
+        assert_eq!(
+            cycle(Some(now + Duration::from_secs(5))).deadline(now),
+            Some(now + Duration::from_secs(5))
+        );
+        assert_eq!(
+            cycle(None).next(now, 10),
+            Some(now + Duration::from_secs(60))
+        );
+        assert_eq!(Timer::Date(now).next(now, 1), None);
+        let repeat_twice = Timer::Cycle {
+            repetitions: Some(2),
+            start: None,
+            interval: Duration::from_secs(60),
+        };
+        assert!(repeat_twice.next(now, 1).is_some());
+        assert_eq!(repeat_twice.next(now, 2), None);
+
+        // Repetitions that elapsed before now count against the cycle
+        let started_before = |repetitions| Timer::Cycle {
+            repetitions: Some(repetitions),
+            start: Some(now - Duration::from_secs(90)),
+            interval: Duration::from_secs(60),
+        };
+        assert_eq!(started_before(3).elapsed(now), 2);
+        assert_eq!(
+            started_before(3).deadline(now),
+            Some(now + Duration::from_secs(30))
+        );
+        assert_eq!(started_before(2).deadline(now), None);
+    }
+}
@@ -0,0 +1,115 @@
+// Generated by snurr. Do not edit.
+
+/// Handlers for the tasks and gateways of the process
+pub trait Handlers<T> {
+    /// Task `Count 1`
+    fn count_1(&self, input: &T) -> snurr::Task;
+
+    /// Task `Count 2`
+    fn count_2(&self, input: &T) -> snurr::Task;
+
+    /// Task `Timeout 1`
+    ///
+    /// Boundaries: (None, Timer)
+    fn timeout_1(&self, input: &T) -> snurr::Task;
+
+    /// Inclusive `RUN ALL`
+    ///
+    /// No flow selects the default flow.
+    fn run_all(&self, input: &T) -> Vec<RunAll>;
+
+    /// Exclusive `RUN DEFAULT`
+    fn run_default(&self, input: &T) -> RunDefault;
+
+    /// Inclusive `RUN A`
+    ///
+    /// No flow selects the default flow.
+    fn run_a(&self, input: &T) -> Vec<RunA>;
+}
+
+/// Outgoing flows of Inclusive `RUN ALL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RunAll {
+    A,
+    B,
+}
+
+impl snurr::FlowChoice for RunAll {
+    const ALL: &'static [Self] = &[RunAll::A, RunAll::B];
+
+    fn flow(&self) -> &'static str {
+        match self {
+            RunAll::A => "A",
+            RunAll::B => "B",
+        }
+    }
+}
+
+/// Outgoing flows of Exclusive `RUN DEFAULT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RunDefault {
+    Yes,
+    No,
+}
+
+impl snurr::FlowChoice for RunDefault {
+    const ALL: &'static [Self] = &[RunDefault::Yes, RunDefault::No];
+
+    fn flow(&self) -> &'static str {
+        match self {
+            RunDefault::Yes => "YES",
+            RunDefault::No => "NO",
+        }
+    }
+}
+
+/// Outgoing flows of Inclusive `RUN A`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RunA {
+    A,
+    B,
+}
+
+impl snurr::FlowChoice for RunA {
+    const ALL: &'static [Self] = &[RunA::A, RunA::B];
+
+    fn flow(&self) -> &'static str {
+        match self {
+            RunA::A => "A",
+            RunA::B => "B",
+        }
+    }
+}
+
+/// Register all handlers on the process builder
+pub fn register<T, H>(builder: snurr::ProcessBuilder<T>, handlers: H) -> snurr::ProcessBuilder<T>
+where
+    H: Handlers<T> + Send + Sync + 'static,
+{
+    let handlers = std::sync::Arc::new(handlers);
+    builder
+        .task("Count 1", {
+            let handlers = std::sync::Arc::clone(&handlers);
+            move |input| handlers.count_1(input)
+        })
+        .task("Count 2", {
+            let handlers = std::sync::Arc::clone(&handlers);
+            move |input| handlers.count_2(input)
+        })
+        .task("Timeout 1", {
+            let handlers = std::sync::Arc::clone(&handlers);
+            move |input| handlers.timeout_1(input)
+        })
+        .inclusive_choice("RUN ALL", {
+            let handlers = std::sync::Arc::clone(&handlers);
+            move |input| handlers.run_all(input)
+        })
+        .exclusive_choice("RUN DEFAULT", {
+            let handlers = std::sync::Arc::clone(&handlers);
+            move |input| handlers.run_default(input)
+        })
+        .inclusive_choice("RUN A", {
+            let handlers = std::sync::Arc::clone(&handlers);
+            move |input| handlers.run_a(input)
+        })
+}
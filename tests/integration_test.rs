@@ -1,11 +1,25 @@
 use snurr::{
-    Exclusive, ProcessBuilder, Symbol, Task,
+    BpmnType, CallResult, Clock, ControlStatus, Controller, Engine, Exclusive, FlowChoice,
+    InstanceStatus, MockClock, ProcessBuilder, ProcessHandlers, Registration, Runtime, Scheduler,
+    Symbol, Task, TokenPosition, Value, Variables,
     error::{
-        BpmnError, BpmnFileError, BpmnFileErrorKind, DiagramError, ParseError, ParseErrorKind,
-        Result, RuntimeError,
+        BpmnError, BpmnFileError, BpmnFileErrorKind, BuildError, DiagramError, ElementError,
+        ParseError, ParseErrorKind, Result, RuntimeError,
     },
 };
-use std::{borrow::Cow, sync::Mutex};
+use std::{
+    borrow::Cow,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU32, Ordering},
+        mpsc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use helpers::{
+    approval_engine, fetch_job, inbox_item, invoice_process, order_process, wait_at, wait_for_token,
+};
 
 const COUNT_1: &str = "Count 1";
 const COUNT_2: &str = "Count 2";
@@ -27,6 +41,87 @@ fn func_cnt(value: u32) -> impl Fn(&Mutex<Counter>) -> Task {
     }
 }
 
+// Processes and helpers shared by the engine tests
+mod helpers {
+    use super::*;
+
+    pub fn order_process(
+        count_1: impl Fn(&Mutex<Counter>) -> Task + Send + Sync + 'static,
+    ) -> Result<snurr::Process<Mutex<Counter>>> {
+        Ok(ProcessBuilder::new("tests/files/message_correlation.bpmn")?
+            .task("Payment received", func_cnt(1))
+            .task(COUNT_1, count_1)
+            .task(COUNT_2, func_cnt(2))
+            .build()?)
+    }
+
+    pub fn invoice_process() -> Result<snurr::Process<Mutex<Counter>>> {
+        Ok(ProcessBuilder::new("tests/files/external_task.bpmn")?
+            .external_task("Send invoice", |data: &Mutex<Counter>| {
+                data.lock().unwrap().count
+            })
+            .task(COUNT_1, func_cnt(1))
+            .task(COUNT_2, func_cnt(2))
+            .build()?)
+    }
+
+    pub fn approval_engine() -> Result<Engine<Mutex<Counter>>> {
+        let engine = Engine::new();
+        engine.deploy(
+            "approval",
+            ProcessBuilder::new("tests/files/user_task.bpmn")?
+                .human_task("Approve order")
+                .human_task("Sign contract")
+                .task(COUNT_1, func_cnt(1))
+                .task(COUNT_2, func_cnt(2))
+                .build()?,
+        );
+        Ok(engine)
+    }
+
+    // Block until a token of the instance waits at the element
+    pub fn wait_at(engine: &Engine<Mutex<Counter>>, id: usize, element: &str) {
+        engine
+            .controller(id)
+            .expect("instance is running")
+            .wait_until(|status| {
+                status
+                    .tokens
+                    .iter()
+                    .any(|token| token.waiting && token.element == element)
+            });
+    }
+
+    pub fn wait_for_token(controller: &Controller, element: &str) -> TokenPosition {
+        let find = |status: &ControlStatus| {
+            status
+                .tokens
+                .iter()
+                .find(|token| token.element == element)
+                .cloned()
+        };
+        find(&controller.wait_until(|status| find(status).is_some()))
+            .expect("token is at the element")
+    }
+
+    pub fn fetch_job(engine: &Engine<Mutex<Counter>>, id: usize, worker: &str) -> snurr::Job {
+        wait_at(engine, id, "Send invoice");
+        engine
+            .fetch_and_lock("Send invoice", worker, 1)
+            .pop()
+            .expect("job is published")
+    }
+
+    pub fn inbox_item(engine: &Engine<Mutex<Counter>>, id: usize, name: &str) -> snurr::InboxItem {
+        wait_at(engine, id, name);
+        engine
+            .inbox()
+            .into_iter()
+            .find(|item| item.name == name)
+            .expect("user task is published")
+    }
+}
+
 #[test]
 fn one_task() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/one_task.bpmn")?
@@ -47,6 +142,67 @@ fn one_task_register_with_bpmn_id() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn typed_and_scoped_registration() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/namespaces.bpmn")?
+        .service_task("Work", func_cnt(100))
+        .task("Work", func_cnt(10))
+        .in_subprocess("First", |builder| builder.task("Count", func_cnt(1)))
+        // Also used by the nested subprocess `Inner`
+        .in_subprocess("Activity_1m8c2jw", |builder| {
+            builder.task("Count", func_cnt(1000))
+        })
+        .task("Count", func_cnt(10000))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 11111);
+    Ok(())
+}
+
+#[test]
+fn scoped_registration_missing() -> Result<()> {
+    let result = ProcessBuilder::<Mutex<Counter>>::new("tests/files/namespaces.bpmn")?
+        .service_task("Work", func_cnt(100))
+        .in_subprocess("First", |builder| builder.task("Count", func_cnt(1)))
+        .build();
+    let Err(BuildError::MissingImplementations(missing)) = result else {
+        panic!("expected missing implementations");
+    };
+    let mut missing: Vec<_> = missing.split(", ").collect();
+    missing.sort();
+    assert_eq!(missing, vec!["Task: Count", "Task: Work"]);
+    Ok(())
+}
+
+#[test]
+fn matching_registration() -> Result<()> {
+    let called = Arc::new(Mutex::new(vec![]));
+    let bpmn = ProcessBuilder::<Mutex<Counter>>::new("tests/files/namespaces.bpmn")?
+        .task_matching(
+            |element| element.bpmn_type == BpmnType::ServiceTask,
+            |input, _| func_cnt(100)(input),
+        )
+        .task_prefix("Wo", |input, _| func_cnt(10)(input))
+        .in_subprocess("First", |builder| {
+            builder.any_task(|input, _| func_cnt(1)(input))
+        })
+        .any_task({
+            let called = Arc::clone(&called);
+            move |input, element| {
+                called.lock().unwrap().push(element.id.to_string());
+                func_cnt(1000)(input)
+            }
+        })
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 2111);
+    assert_eq!(
+        *called.lock().unwrap(),
+        vec!["Activity_0h9j3mf", "Activity_1z6v0se"]
+    );
+    Ok(())
+}
+
 #[test]
 fn two_task() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/two_task.bpmn")?
@@ -79,6 +235,41 @@ fn subprocess_nested() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn run_from_element() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/subprocess.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .build()?;
+    for (element, count) in [(COUNT_1, 3), (COUNT_2, 2), ("Activity_1b4bocv", 2)] {
+        let result = bpmn.run_from(element, Default::default())?;
+        assert_eq!(result.lock().unwrap().count, count);
+    }
+    assert!(matches!(
+        bpmn.run_from("Flow_0kwzv0p", Default::default()),
+        Err(RuntimeError::Diagram(DiagramError::MissingElement(element))) if element == "Flow_0kwzv0p"
+    ));
+    Ok(())
+}
+
+#[test]
+fn run_from_nested_subprocess() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/subprocess_nested.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .build()?;
+    // Elements of the main process are found before elements of subprocesses with the same name
+    for (element, count) in [
+        (COUNT_1, 1),
+        ("Activity_1cj6xm1", 2),
+        ("Activity_0jfgr9s", 3),
+        ("Start 2", 3),
+    ] {
+        let result = bpmn.run_from(element, Default::default())?;
+        assert_eq!(result.lock().unwrap().count, count);
+    }
+    Ok(())
+}
+
 #[test]
 fn subprocess_message_end() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/subprocess_message_end.bpmn")?
@@ -129,6 +320,95 @@ fn exclusive_gateway() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn mutable_data() -> Result<()> {
+    let mut runs = 0;
+    let bpmn = ProcessBuilder::<Counter>::new_mut("tests/files/exclusive_gateway.bpmn")?
+        .task_mut(COUNT_1, move |input| {
+            runs += 1;
+            input.count += runs;
+            Default::default()
+        })
+        .task_mut(COUNT_2, |input| {
+            input.count += 2;
+            Default::default()
+        })
+        .task_mut(COUNT_3, |input| {
+            input.count += 3;
+            Default::default()
+        })
+        .exclusive_mut("CHOOSE", |input| {
+            if input.count == 1 { "YES" } else { "NO" }.into()
+        })
+        .build()?;
+
+    // The state of the FnMut is kept between runs
+    assert_eq!(bpmn.run_mut(Counter::default())?.count, 3);
+    assert_eq!(bpmn.run_mut(Counter::default())?.count, 5);
+    Ok(())
+}
+
+#[test]
+fn variables_mapping() -> Result<()> {
+    let bpmn = ProcessBuilder::<Variables>::new("tests/files/variables.bpmn")?
+        .variable_scopes()
+        .task("Price", |input| {
+            // Only the mapped inputs are visible
+            assert_eq!(input.value("amount"), None);
+            let quantity = input.get::<i64>("quantity").unwrap_or_default();
+            let unit = input.get::<i64>("unit").unwrap_or_default();
+            input.set("price", quantity * unit);
+            input.set("discount", 5);
+            Default::default()
+        })
+        .task("Add shipping", |input| {
+            input.set_local("fee", 5);
+            let total = input.get::<i64>("total").unwrap_or_default();
+            let fee = input.get::<i64>("fee").unwrap_or_default();
+            input.set("total", total + fee);
+            Default::default()
+        })
+        .task("Invoice", |input| {
+            assert_eq!(input.value("amount"), None);
+            let total = input.get::<i64>("total").unwrap_or_default();
+            input.set("invoice", format!("total {total}"));
+            Default::default()
+        })
+        .build()?;
+
+    let result = bpmn.run(Variables::new().with("amount", 3).with("unit", 10))?;
+    assert_eq!(result.get::<i64>("total"), Some(35));
+    assert_eq!(result.get::<String>("invoice").as_deref(), Some("total 35"));
+    assert_eq!(result.value("amount"), Some(Value::Int(3)));
+    // Unmapped outputs and subprocess locals are not kept
+    assert_eq!(
+        result.to_map().keys().collect::<Vec<_>>(),
+        ["amount", "invoice", "total", "unit"]
+    );
+    Ok(())
+}
+
+#[test]
+fn variables_without_scopes() -> Result<()> {
+    let bpmn = ProcessBuilder::<Variables>::new("tests/files/variables.bpmn")?
+        .task("Price", |input| {
+            input.set("seen", input.get::<i64>("amount").is_some());
+            Default::default()
+        })
+        .task("Add shipping", |input| {
+            input.set_local("fee", 5);
+            Default::default()
+        })
+        .task("Invoice", |_| Default::default())
+        .build()?;
+
+    // Mappings are ignored and all tasks share the variables
+    let result = bpmn.run(Variables::new().with("amount", 3))?;
+    assert_eq!(result.get::<bool>("seen"), Some(true));
+    assert_eq!(result.get::<i64>("fee"), Some(5));
+    Ok(())
+}
+
 #[test]
 fn exclusive_gateway_with_id() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
@@ -198,6 +478,118 @@ fn inclusive_gateway() -> Result<()> {
     Ok(())
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choose {
+    Yes,
+    No,
+}
+
+impl FlowChoice for Choose {
+    const ALL: &'static [Self] = &[Choose::Yes, Choose::No];
+
+    fn flow(&self) -> &'static str {
+        match self {
+            Choose::Yes => "YES",
+            Choose::No => "NO",
+        }
+    }
+}
+
+#[test]
+fn exclusive_gateway_flow_choice() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .exclusive_choice("CHOOSE", |_| Choose::Yes)
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    Ok(())
+}
+
+#[test]
+fn inclusive_gateway_flow_choice() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/inclusive_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .inclusive_choice("CHOOSE", |_| vec![Choose::Yes, Choose::No])
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 7);
+    Ok(())
+}
+
+#[test]
+fn flow_choice_unknown_flow() -> Result<()> {
+    #[derive(Debug, Clone, Copy)]
+    enum Maybe {
+        Yes,
+        Maybe,
+    }
+
+    impl FlowChoice for Maybe {
+        const ALL: &'static [Self] = &[Maybe::Yes, Maybe::Maybe];
+
+        fn flow(&self) -> &'static str {
+            match self {
+                Maybe::Yes => "YES",
+                Maybe::Maybe => "MAYBE",
+            }
+        }
+    }
+
+    let result = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .exclusive_choice("CHOOSE", |_| Maybe::Yes)
+        .build();
+    assert!(matches!(
+        result,
+        Err(BuildError::UnknownFlows(flows)) if flows == "Exclusive `CHOOSE`: MAYBE"
+    ));
+    Ok(())
+}
+
+struct Counting {
+    factor: u32,
+}
+
+impl Counting {
+    fn count(&self, input: &Mutex<Counter>, value: u32) -> Task {
+        input.lock().unwrap().count += value * self.factor;
+        Default::default()
+    }
+
+    fn choose(&self, _input: &Mutex<Counter>) -> Choose {
+        Choose::Yes
+    }
+}
+
+impl ProcessHandlers<Mutex<Counter>> for Counting {
+    fn register(
+        registration: Registration<Mutex<Counter>, Self>,
+    ) -> Registration<Mutex<Counter>, Self> {
+        registration
+            .task(COUNT_1, |handlers, input| handlers.count(input, 1))
+            .task(COUNT_2, |handlers, input| handlers.count(input, 2))
+            .task(COUNT_3, |handlers, input| handlers.count(input, 3))
+            .exclusive_choice("CHOOSE", Self::choose)
+    }
+}
+
+#[test]
+fn process_handlers() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
+        .handlers(Counting { factor: 2 })
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 6);
+    Ok(())
+}
+
 #[test]
 fn inclusive_gateway_same_flow_used_multiple_times() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/inclusive_gateway.bpmn")?
@@ -271,82 +663,602 @@ fn inclusive_join_fork_gwb_one_flow() -> Result<()> {
 }
 
 #[test]
-fn inclusive_join_fork_gateway_verify_sync() -> Result<()> {
-    let bpmn = ProcessBuilder::new("tests/files/inclusive_join_fork.bpmn")?
+fn inclusive_join_fork_gateway_verify_sync() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/inclusive_join_fork.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .inclusive("GW A", |_| vec!["A", "B"].into())
+        .inclusive("GW B", |input| {
+            // Make sure that the gateway is only executed once. Do not recommend to mutate data in gateway.
+            input.lock().unwrap().count += 1;
+            vec!["A", "B", "C"].into()
+        })
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 7);
+    Ok(())
+}
+
+#[test]
+fn parallel_inclusive_join_fork() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/parallel_inclusive_join_fork.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .inclusive("GW A", |_| vec!["A", "B"].into())
+        .inclusive("GW AA", |_| vec!["A", "B", "C"].into())
+        .inclusive("GW AAA", |_| vec!["A", "B", "C", "D", "E"].into())
+        .inclusive("GW B", |_| vec!["A", "B", "C"].into())
+        .inclusive("GW BB", |_| vec!["A", "B"].into())
+        .inclusive("GW BBB", |_| vec!["A", "B", "C", "D"].into())
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 23);
+    Ok(())
+}
+
+#[test]
+fn inclusive_with_parallel() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/inclusive_with_parallel.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .inclusive("GW A", |_| vec!["A", "B"].into())
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 5);
+    Ok(())
+}
+
+#[test]
+fn parallell_gateway() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/parallell_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 10);
+    Ok(())
+}
+
+#[test]
+fn parallell_gateway_branch_data() -> Result<()> {
+    let joined = Arc::new(Mutex::new(Vec::new()));
+    let bpmn = ProcessBuilder::new("tests/files/parallell_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .branch_data(
+            |input: &Mutex<Counter>| {
+                Mutex::new(Counter {
+                    count: input.lock().unwrap().count,
+                })
+            },
+            {
+                let joined = Arc::clone(&joined);
+                move |gateway, branches| {
+                    let mut counts: Vec<_> = branches
+                        .into_iter()
+                        .map(|branch| branch.into_inner().unwrap().count)
+                        .collect();
+                    counts.sort();
+                    joined
+                        .lock()
+                        .unwrap()
+                        .push((gateway.to_string(), counts.clone()));
+                    Mutex::new(Counter {
+                        count: counts.iter().sum(),
+                    })
+                }
+            },
+        )
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+
+    // Every branch started with the count of the forking token
+    assert_eq!(result.lock().unwrap().count, 12);
+    assert_eq!(
+        *joined.lock().unwrap(),
+        vec![("Gateway_086ov5i".to_string(), vec![3, 4, 5])]
+    );
+    Ok(())
+}
+
+#[test]
+fn inclusive_gateway_branch_data() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/inclusive_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .inclusive("CHOOSE", |_| vec!["YES", "NO"].into())
+        .branch_data(
+            |_: &Mutex<Counter>| Default::default(),
+            |_, branches| {
+                assert_eq!(branches.len(), 2);
+                Mutex::new(Counter {
+                    count: branches
+                        .into_iter()
+                        .map(|branch| branch.into_inner().unwrap().count)
+                        .max()
+                        .unwrap_or_default(),
+                })
+            },
+        )
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 4);
+    Ok(())
+}
+
+#[test]
+fn error_handling() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/error_handling.bpmn")?
+        .task(COUNT_1, |_| Symbol::Error.into())
+        .task(COUNT_2, |_| Symbol::Error.into())
+        .task(COUNT_3, func_cnt(3))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    Ok(())
+}
+
+#[test]
+fn subprocess_nested_error_propagation() -> Result<()> {
+    for (fail, count) in [("YES", 5), ("NO", 3)] {
+        let bpmn = ProcessBuilder::new("tests/files/nested_error_propagation.bpmn")?
+            .task(COUNT_1, func_cnt(1))
+            .task(COUNT_2, func_cnt(2))
+            .task(COUNT_3, func_cnt(3))
+            .exclusive("Fail?", move |_| fail.into())
+            .build()?;
+        let result = bpmn.run(Default::default())?;
+        assert_eq!(result.lock().unwrap().count, count);
+    }
+    Ok(())
+}
+
+#[test]
+fn subprocess_nested_error_uncaught() -> Result<()> {
+    let bpmn = ProcessBuilder::<Counter>::new("tests/files/nested_error_uncaught.bpmn")?.build()?;
+    match bpmn.run(Default::default()) {
+        Err(RuntimeError::Uncaught(end)) => {
+            assert_eq!(end.name.as_deref(), Some("Failure"));
+            assert_eq!(end.symbol, Symbol::Error);
+        }
+        other => panic!("Expected uncaught error, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn event_subprocess_catch_error() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/event_subprocess.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_4, func_cnt(4))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 7);
+    Ok(())
+}
+
+#[test]
+fn error_code_boundary() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/error_code.bpmn")?
+        .task("Work", |_| Task::error_code("E42"))
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 2);
+    Ok(())
+}
+
+#[test]
+fn error_code_catch_all_boundary() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/error_code.bpmn")?
+        .task("Work", |_| Task::error_code("E99"))
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    Ok(())
+}
+
+#[test]
+fn error_code_boundary_name_must_match() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/error_code.bpmn")?
+        .task("Work", |_| ("Typo", Symbol::Error).into())
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .build()?;
+    let result = bpmn.run(Default::default());
+    assert!(matches!(
+        result,
+        Err(RuntimeError::Diagram(DiagramError::MissingBoundary(boundary, _))) if boundary.contains("Typo")
+    ));
+    Ok(())
+}
+
+#[test]
+fn error_code_subprocess_end() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/error_code.bpmn")?
+        .task("Work", |_| Default::default())
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 4);
+    Ok(())
+}
+
+#[test]
+fn two_boundary_timer_thrown() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/two_boundary.bpmn")?
+        .task(COUNT_1, |_| ("Timeout", Symbol::Timer).into())
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    Ok(())
+}
+
+#[test]
+fn timer_boundary_timeout() -> Result<()> {
+    let clock = MockClock::new(SystemTime::now());
+    let task_clock = clock.clone();
+    let bpmn = ProcessBuilder::new("tests/files/timer_boundary_timeout.bpmn")?
+        .task(COUNT_1, move |_| {
+            // Wait for the timer boundary to be scheduled and then pass its deadline
+            while task_clock.pending() == 0 {
+                std::thread::yield_now();
+            }
+            task_clock.advance(Duration::from_secs(1));
+            Default::default()
+        })
+        .task(COUNT_3, func_cnt(3))
+        .clock(clock)
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    Ok(())
+}
+
+#[test]
+fn timer_boundary_completed_in_time() -> Result<()> {
+    let clock = MockClock::new(SystemTime::now());
+    let bpmn = ProcessBuilder::new("tests/files/timer_boundary_timeout.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_3, func_cnt(3))
+        .clock(clock.clone())
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 1);
+    // The timer of the boundary is cancelled when the task completes
+    assert_eq!(clock.pending(), 0);
+    Ok(())
+}
+
+#[test]
+fn timer_intermediate_catch() -> Result<()> {
+    // 2026-10-18T12:00:00Z
+    let start = UNIX_EPOCH + Duration::from_secs(1_792_324_800);
+    let clock = MockClock::auto_advance(start);
+    let bpmn = ProcessBuilder::new("tests/files/timer_catch.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .clock(clock.clone())
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    // Waited until the timer date the next day
+    assert_eq!(clock.now(), start + Duration::from_secs(24 * 60 * 60));
+    Ok(())
+}
+
+#[test]
+fn invalid_timer_definition() -> Result<()> {
+    let bpmn = include_str!("files/timer_catch.bpmn").replace("PT1H", "one hour");
+    match bpmn.parse::<ProcessBuilder<Counter>>() {
+        Err(error) => assert!(
+            matches!(
+                error,
+                ParseError {
+                    source: ParseErrorKind::Bpmn(BpmnError::InvalidTimer(_)),
+                    ..
+                }
+            ),
+            "Expected invalid timer"
+        ),
+        _ => panic!("Expected an error"),
+    }
+    Ok(())
+}
+
+#[test]
+fn timer_start_scheduler() -> Result<()> {
+    let clock = MockClock::new(SystemTime::now());
+    let bpmn = ProcessBuilder::new("tests/files/timer_start.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .clock(clock.clone())
+        .build()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let scheduler = Scheduler::new();
+    let ids = scheduler.schedule(Arc::new(bpmn), Mutex::<Counter>::default, move |result| {
+        let count = result.map(|data| data.into_inner().unwrap().count);
+        sender.send(count).unwrap();
+    });
+    assert_eq!(ids.len(), 1);
+    assert_eq!(scheduler.list()[0].start_event, "Every hour");
+
+    // Nothing starts before the first hour
+    clock.advance(Duration::from_secs(30 * 60));
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+
+    // Cycle with 3 repetitions
+    clock.advance(Duration::from_secs(5 * 60 * 60));
+    for _ in 0..3 {
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap()?, 1);
+    }
+    assert!(scheduler.list().is_empty());
+    Ok(())
+}
+
+#[test]
+fn timer_start_scheduler_stop() -> Result<()> {
+    let clock = MockClock::new(SystemTime::now());
+    let bpmn = ProcessBuilder::new("tests/files/timer_start.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .clock(clock.clone())
+        .build()?;
+
+    let (sender, receiver) = mpsc::channel();
+    let scheduler = Scheduler::new();
+    let ids = scheduler.schedule(Arc::new(bpmn), Mutex::<Counter>::default, move |result| {
+        sender.send(result.is_ok()).unwrap();
+    });
+
+    clock.advance(Duration::from_secs(60 * 60));
+    assert!(receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+    assert_eq!(scheduler.list()[0].started, 1);
+
+    assert!(scheduler.stop(ids[0]));
+    assert_eq!(clock.pending(), 0);
+    clock.advance(Duration::from_secs(2 * 60 * 60));
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+    assert!(scheduler.list().is_empty());
+    Ok(())
+}
+
+#[test]
+fn timer_start_scheduler_auto_advance() -> Result<()> {
+    let clock = MockClock::auto_advance(SystemTime::now());
+    let bpmn = ProcessBuilder::new("tests/files/timer_start_unbounded.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .clock(clock.clone())
+        .build()?;
+
+    // Every deadline of the unbounded cycle is reached at once, until the schedule is stopped
+    let (sender, receiver) = mpsc::channel();
+    let scheduler = Scheduler::new();
+    let stopper = scheduler.clone();
+    let started = Arc::new(AtomicU32::new(0));
+    scheduler.schedule(
+        Arc::new(bpmn),
+        move || {
+            if started.fetch_add(1, Ordering::Relaxed) == 2 {
+                stopper.stop_all();
+            }
+            Mutex::<Counter>::default()
+        },
+        move |result| {
+            sender.send(result.is_ok()).unwrap();
+        },
+    );
+
+    for _ in 0..3 {
+        assert!(receiver.recv_timeout(Duration::from_secs(5)).unwrap());
+    }
+    assert!(receiver.recv_timeout(Duration::from_millis(50)).is_err());
+    assert!(scheduler.list().is_empty());
+    assert_eq!(clock.pending(), 0);
+    Ok(())
+}
+
+#[test]
+fn timer_start_run_without_none_start() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/timer_start.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .build()?;
+    assert!(matches!(
+        bpmn.run(Default::default()),
+        Err(RuntimeError::Diagram(DiagramError::MissingStartEvent(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn multiple_start_events() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/multiple_start.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .build()?;
+    let count = |result: Mutex<Counter>| result.into_inner().unwrap().count;
+    assert_eq!(count(bpmn.run(Default::default())?), 1);
+    assert_eq!(count(bpmn.run_from_start("Start", Default::default())?), 1);
+    assert_eq!(count(bpmn.run_from_start("Alarm", Default::default())?), 3);
+    assert_eq!(
+        count(bpmn.run_from_start("Event_1r8n5uc", Default::default())?),
+        2
+    );
+    assert_eq!(
+        count(bpmn.run_on_message("Order received", Default::default())?),
+        2
+    );
+    Ok(())
+}
+
+#[test]
+fn multiple_start_events_no_match() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/multiple_start.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .build()?;
+    for result in [
+        bpmn.run_from_start("Unknown", Default::default()),
+        // Not a message start event
+        bpmn.run_on_message("Alarm", Default::default()),
+    ] {
+        assert!(matches!(
+            result,
+            Err(RuntimeError::Diagram(DiagramError::MissingStartEvent(_)))
+        ));
+    }
+    Ok(())
+}
+
+#[test]
+fn intercept_calls() -> Result<()> {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
         .task(COUNT_1, func_cnt(1))
-        .inclusive("GW A", |_| vec!["A", "B"].into())
-        .inclusive("GW B", |input| {
-            // Make sure that the gateway is only executed once. Do not recommend to mutate data in gateway.
-            input.lock().unwrap().count += 1;
-            vec!["A", "B", "C"].into()
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .exclusive("CHOOSE", |_| "YES".into())
+        .intercept({
+            let calls = Arc::clone(&calls);
+            move |_, element, next| {
+                calls
+                    .lock()
+                    .unwrap()
+                    .push(format!("outer {}", element.bpmn_type));
+                next()
+            }
+        })
+        .intercept({
+            let calls = Arc::clone(&calls);
+            move |_, element, next| {
+                let result = next();
+                calls.lock().unwrap().push(format!(
+                    "inner {} {result:?}",
+                    element.name.unwrap_or(element.id)
+                ));
+                result
+            }
         })
         .build()?;
     let result = bpmn.run(Default::default())?;
-    assert_eq!(result.lock().unwrap().count, 7);
+    assert_eq!(result.lock().unwrap().count, 3);
+    let calls = calls.lock().unwrap();
+    assert_eq!(calls[0], "outer Task");
+    assert_eq!(calls[1], "inner Count 1 Task(Default)");
+    assert!(calls.contains(&"outer Exclusive".to_string()));
+    assert!(calls.contains(&r#"inner CHOOSE Exclusive(Flow("YES"))"#.to_string()));
     Ok(())
 }
 
 #[test]
-fn parallel_inclusive_join_fork() -> Result<()> {
-    let bpmn = ProcessBuilder::new("tests/files/parallel_inclusive_join_fork.bpmn")?
+fn intercept_panic() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
         .task(COUNT_1, func_cnt(1))
-        .inclusive("GW A", |_| vec!["A", "B"].into())
-        .inclusive("GW AA", |_| vec!["A", "B", "C"].into())
-        .inclusive("GW AAA", |_| vec!["A", "B", "C", "D", "E"].into())
-        .inclusive("GW B", |_| vec!["A", "B", "C"].into())
-        .inclusive("GW BB", |_| vec!["A", "B"].into())
-        .inclusive("GW BBB", |_| vec!["A", "B", "C", "D"].into())
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .exclusive("CHOOSE", |_| "YES".into())
+        .intercept(|_, element, next| match element.bpmn_type {
+            BpmnType::Exclusive => CallResult::panic("not authorized"),
+            _ => next(),
+        })
         .build()?;
-    let result = bpmn.run(Default::default())?;
-    assert_eq!(result.lock().unwrap().count, 23);
+    let result = bpmn.run(Default::default());
+    assert!(
+        matches!(result, Err(RuntimeError::Panic(error)) if error.to_string() == "not authorized")
+    );
     Ok(())
 }
 
 #[test]
-fn inclusive_with_parallel() -> Result<()> {
-    let bpmn = ProcessBuilder::new("tests/files/inclusive_with_parallel.bpmn")?
+fn intercept_boundary() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/two_boundary.bpmn")?
         .task(COUNT_1, func_cnt(1))
-        .inclusive("GW A", |_| vec!["A", "B"].into())
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .intercept(|_, element, next| match element.name {
+            Some(COUNT_1) => CallResult::Task(("Error", Symbol::Error).into()),
+            _ => next(),
+        })
         .build()?;
     let result = bpmn.run(Default::default())?;
-    assert_eq!(result.lock().unwrap().count, 5);
+    assert_eq!(result.lock().unwrap().count, 2);
     Ok(())
 }
 
 #[test]
-fn parallell_gateway() -> Result<()> {
-    let bpmn = ProcessBuilder::new("tests/files/parallell_gateway.bpmn")?
-        .task(COUNT_1, func_cnt(1))
+fn try_task_error() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
+        .try_task(COUNT_1, |input: &Mutex<Counter>| {
+            let count: u32 = "one".parse()?;
+            func_cnt(count)(input);
+            Ok::<_, std::num::ParseIntError>(Task::Default)
+        })
         .task(COUNT_2, func_cnt(2))
         .task(COUNT_3, func_cnt(3))
-        .task(COUNT_4, func_cnt(4))
+        .exclusive("CHOOSE", |_| "YES".into())
         .build()?;
-    let result = bpmn.run(Default::default())?;
-    assert_eq!(result.lock().unwrap().count, 10);
+    let Err(RuntimeError::Panic(error)) = bpmn.run(Default::default()) else {
+        panic!("expected a panic");
+    };
+    let error = error.downcast_ref::<ElementError>().unwrap();
+    assert_eq!(error.name.as_deref(), Some(COUNT_1));
+    assert_eq!(error.bpmn_type, BpmnType::Task);
+    assert_eq!(error.error.to_string(), "invalid digit found in string");
     Ok(())
 }
 
 #[test]
-fn error_handling() -> Result<()> {
-    let bpmn = ProcessBuilder::new("tests/files/error_handling.bpmn")?
-        .task(COUNT_1, |_| Symbol::Error.into())
-        .task(COUNT_2, |_| Symbol::Error.into())
+fn try_exclusive_error() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/exclusive_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
         .task(COUNT_3, func_cnt(3))
+        .try_exclusive("CHOOSE", |_| Err("no choice"))
+        .error_boundary(|_| Some(Symbol::Error.into()))
         .build()?;
-    let result = bpmn.run(Default::default())?;
-    assert_eq!(result.lock().unwrap().count, 3);
+    let Err(RuntimeError::Panic(error)) = bpmn.run(Default::default()) else {
+        panic!("expected a panic");
+    };
+    assert_eq!(error.to_string(), "Exclusive `CHOOSE`: no choice");
     Ok(())
 }
 
 #[test]
-fn two_boundary_timer_thrown() -> Result<()> {
+fn try_task_error_boundary() -> Result<()> {
+    let errors = Arc::new(Mutex::new(vec![]));
     let bpmn = ProcessBuilder::new("tests/files/two_boundary.bpmn")?
-        .task(COUNT_1, |_| ("Timeout", Symbol::Timer).into())
+        .try_task(COUNT_1, |_| Err("overflow"))
         .task(COUNT_2, func_cnt(2))
         .task(COUNT_3, func_cnt(3))
+        .error_boundary({
+            let errors = Arc::clone(&errors);
+            move |error| {
+                errors.lock().unwrap().push(error.to_string());
+                Some(("Error", Symbol::Error).into())
+            }
+        })
         .build()?;
     let result = bpmn.run(Default::default())?;
-    assert_eq!(result.lock().unwrap().count, 3);
+    assert_eq!(result.lock().unwrap().count, 2);
+    assert_eq!(*errors.lock().unwrap(), vec!["Task `Count 1`: overflow"]);
     Ok(())
 }
 
@@ -434,6 +1346,73 @@ fn showcase() -> Result<()> {
     Ok(())
 }
 
+// Generated with `ProcessBuilder::codegen` from showcase.bpmn
+mod showcase {
+    include!("files/showcase_handlers.rs");
+}
+
+struct Showcase;
+
+impl showcase::Handlers<Mutex<Counter>> for Showcase {
+    fn count_1(&self, input: &Mutex<Counter>) -> Task {
+        func_cnt(1)(input)
+    }
+
+    fn count_2(&self, input: &Mutex<Counter>) -> Task {
+        func_cnt(2)(input)
+    }
+
+    fn timeout_1(&self, _: &Mutex<Counter>) -> Task {
+        Symbol::Timer.into()
+    }
+
+    fn run_all(&self, _: &Mutex<Counter>) -> Vec<showcase::RunAll> {
+        vec![showcase::RunAll::A, showcase::RunAll::B]
+    }
+
+    fn run_default(&self, _: &Mutex<Counter>) -> showcase::RunDefault {
+        showcase::RunDefault::No
+    }
+
+    fn run_a(&self, _: &Mutex<Counter>) -> Vec<showcase::RunA> {
+        vec![showcase::RunA::A]
+    }
+}
+
+#[test]
+fn codegen_handlers() -> Result<()> {
+    let bpmn =
+        showcase::register(ProcessBuilder::new("tests/files/showcase.bpmn")?, Showcase).build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 16);
+    Ok(())
+}
+
+#[test]
+fn codegen_is_current() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let path = std::env::temp_dir().join(format!("snurr_showcase_{}.rs", std::process::id()));
+    ProcessBuilder::<()>::new("tests/files/showcase.bpmn")?.codegen(&path)?;
+    let generated = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(generated, include_str!("files/showcase_handlers.rs"));
+    Ok(())
+}
+
+#[test]
+fn codegen_identifier_collision() -> Result<()> {
+    let path = std::env::temp_dir().join(format!("snurr_collision_{}.rs", std::process::id()));
+    let error = ProcessBuilder::<()>::new("tests/files/codegen_collision.bpmn")?
+        .codegen(&path)
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(
+        error.to_string(),
+        "`Prüfen` and `Präfen` both map to the identifier `pr_fen`"
+    );
+    assert!(!path.exists());
+    Ok(())
+}
+
 #[test]
 fn task_fork() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/task_fork.bpmn")?
@@ -573,6 +1552,59 @@ fn event_gateway() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn event_gateway_missing_function() -> Result<()> {
+    // The timer event of the senior gatekeeper has no timer definition, so it can't wait without a function
+    let result = ProcessBuilder::<Mutex<Counter>>::new("tests/files/event_gateway.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_3, func_cnt(3))
+        .task("Investigate", |_| Default::default())
+        .build();
+    let Err(BuildError::MissingImplementations(missing)) = result else {
+        panic!("expected missing implementations");
+    };
+    assert_eq!(missing, "EventBased: SENIOR GATEKEEPER");
+    Ok(())
+}
+
+#[test]
+fn event_gateway_wait_signal() -> Result<()> {
+    let clock = MockClock::new(UNIX_EPOCH);
+    let bpmn = ProcessBuilder::new("tests/files/event_gateway_wait.bpmn")?
+        .clock(clock.clone())
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_4, func_cnt(4))
+        .task("Payment", |_| Default::default())
+        .exclusive("Signal?", |_| "Yes".into())
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 2);
+    // The timer of the gateway is cancelled when the signal is caught
+    assert_eq!(clock.pending(), 0);
+    Ok(())
+}
+
+#[test]
+fn event_gateway_wait_timer() -> Result<()> {
+    let clock = MockClock::auto_advance(UNIX_EPOCH);
+    let bpmn = ProcessBuilder::new("tests/files/event_gateway_wait.bpmn")?
+        .clock(clock.clone())
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_4, func_cnt(4))
+        .task("Payment", |_| Default::default())
+        .exclusive("Signal?", |_| "No".into())
+        .build()?;
+
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 1);
+    // The gateway waited an hour for the timer
+    assert_eq!(clock.now(), UNIX_EPOCH + Duration::from_secs(3600));
+    Ok(())
+}
+
 #[test]
 fn single_flow() -> Result<()> {
     let bpmn = ProcessBuilder::new("tests/files/single_flow.bpmn")?
@@ -613,6 +1645,36 @@ fn terminate_event_sub_process() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn outcome_terminate_event() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/terminate_event.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .exclusive("Terminate?", |_| "YES".into())
+        .build()?;
+    let (_, outcome) = bpmn.run_with_outcome(Default::default())?;
+    assert!(outcome.terminated());
+    assert!(outcome.reached("Event_19e4as5"));
+    assert!(outcome.reached_symbol(Symbol::Terminate));
+    Ok(())
+}
+
+#[test]
+fn outcome_end_event_with_symbol() -> Result<()> {
+    let bpmn =
+        ProcessBuilder::<Counter>::new("tests/files/process_end_with_symbol.bpmn")?.build()?;
+    let (_, outcome) = bpmn.run_with_outcome(Default::default())?;
+    assert!(!outcome.terminated());
+    match outcome.end_events.as_slice() {
+        [end] => {
+            assert_eq!(end.id, "Event_098hq61");
+            assert_eq!(end.name, None);
+            assert_eq!(end.symbol, Symbol::Message);
+        }
+        ends => panic!("Expected one end event, got {ends:?}"),
+    }
+    Ok(())
+}
+
 #[test]
 fn startevent_not_first() -> Result<()> {
     // StartEvent out of order in XML file
@@ -752,6 +1814,82 @@ fn intermediate_catch_event() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn signal_catch_parallel_branch() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/signal_catch.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    Ok(())
+}
+
+#[test]
+fn signal_catch_stalled() -> Result<()> {
+    let bpmn = ProcessBuilder::<()>::new("tests/files/signal_stalled.bpmn")?.build()?;
+    match bpmn.run(()) {
+        Err(RuntimeError::Stalled(waiting)) => assert_eq!(waiting, vec!["Go"]),
+        other => panic!("expected stalled process, got {other:?}"),
+    }
+    Ok(())
+}
+
+#[test]
+fn signal_catch_without_throw() -> Result<()> {
+    // Nothing in the process throws the signal, so the catch event is passed
+    let bpmn = ProcessBuilder::<()>::new("tests/files/signal_unthrown.bpmn")?.build()?;
+    bpmn.run(())?;
+    Ok(())
+}
+
+#[test]
+fn signal_catch_unnamed() -> Result<()> {
+    // An unnamed signal has nothing to wait on, so the catch event is passed, even in an engine
+    let engine = Engine::new();
+    engine.deploy(
+        "unnamed",
+        ProcessBuilder::new("tests/files/signal_unnamed.bpmn")?
+            .task(COUNT_1, func_cnt(1))
+            .intermediate_catch_event(|input: &Mutex<Counter>, name, symbol| {
+                assert_eq!((name, symbol), (None, Symbol::Signal));
+                input.lock().unwrap().count += 10;
+                Ok(())
+            })
+            .build()?,
+    );
+    let id = engine.start("unnamed", Default::default())?;
+    let result = engine.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 11);
+    Ok(())
+}
+
+#[test]
+fn signal_boundary_interrupts() -> Result<()> {
+    // Work only completes once the signal boundary has been followed, or after a long timeout
+    let (interrupted, released) = mpsc::channel();
+    let released = Mutex::new(released);
+    let bpmn = ProcessBuilder::new("tests/files/signal_boundary.bpmn")?
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, move |input| {
+            let _ = interrupted.send(());
+            func_cnt(2)(input)
+        })
+        .task(COUNT_3, func_cnt(3))
+        .task(COUNT_4, func_cnt(4))
+        .task("Work", move |_| {
+            let _ = released
+                .lock()
+                .unwrap()
+                .recv_timeout(Duration::from_secs(10));
+            Default::default()
+        })
+        .build()?;
+    let result = bpmn.run(Default::default())?;
+    assert_eq!(result.lock().unwrap().count, 7);
+    Ok(())
+}
+
 #[test]
 #[cfg(debug_assertions)]
 fn parallel_unbalanced() -> Result<()> {
@@ -785,3 +1923,433 @@ fn parallel_unbalanced2() -> Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn runtime_correlate_message() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/message_correlation.bpmn")?
+        .task("Payment received", func_cnt(1))
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .build()?;
+    let runtime = Runtime::new(Arc::new(bpmn));
+    let id = runtime.start_with_key("order-42", Default::default());
+
+    // Shipped arrives before the token waits on it and is kept until caught
+    runtime.correlate_message("order-42", "Shipped", |data: &Mutex<Counter>| {
+        data.lock().unwrap().count += 10
+    })?;
+    runtime.correlate_message(id, "Payment received", |data: &Mutex<Counter>| {
+        data.lock().unwrap().count += 20
+    })?;
+    let result = runtime.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 34);
+    assert!(runtime.wait(id).is_none());
+    Ok(())
+}
+
+#[test]
+fn runtime_correlate_message_event_gateway() -> Result<()> {
+    let bpmn = ProcessBuilder::new("tests/files/event_gateway_wait.bpmn")?
+        .clock(MockClock::new(UNIX_EPOCH))
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .task(COUNT_4, func_cnt(4))
+        .task("Payment", |_| Default::default())
+        .exclusive("Signal?", |_| "No".into())
+        .build()?;
+    let runtime = Runtime::new(Arc::new(bpmn));
+    let id = runtime.start(Default::default());
+    runtime.correlate_message(id, "Payment", |_| {})?;
+    let result = runtime.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 4);
+    Ok(())
+}
+
+#[test]
+fn runtime_correlate_unknown_instance() -> Result<()> {
+    let bpmn = ProcessBuilder::<Mutex<Counter>>::new("tests/files/message_correlation.bpmn")?
+        .task("Payment received", func_cnt(1))
+        .task(COUNT_1, func_cnt(1))
+        .task(COUNT_2, func_cnt(2))
+        .build()?;
+    let runtime = Runtime::new(Arc::new(bpmn));
+    let result = runtime.correlate_message("missing", "Shipped", |_| {});
+    assert!(matches!(result, Err(RuntimeError::UnknownInstance(key)) if key == "missing"));
+    Ok(())
+}
+
+#[test]
+fn engine_instances() -> Result<()> {
+    let engine = Arc::new(Engine::new());
+    engine.deploy("order", order_process(func_cnt(1))?);
+    engine.deploy(
+        "signal",
+        ProcessBuilder::new("tests/files/signal_stalled.bpmn")?.build()?,
+    );
+    assert_eq!(engine.deployed(), vec!["order", "signal"]);
+    assert!(matches!(
+        engine.start("missing", Default::default()),
+        Err(RuntimeError::UnknownProcess(key)) if key == "missing"
+    ));
+
+    let signal = engine.start("signal", Default::default())?;
+    let order = engine.start_with_key("order", "order-1", Default::default())?;
+    wait_at(&engine, signal, "Go");
+    wait_at(&engine, order, "Payment received");
+    assert_eq!(engine.status(signal), Some(InstanceStatus::Waiting));
+    let list = engine.list();
+    assert_eq!(list.len(), 2);
+    assert_eq!(
+        (list[1].process.as_str(), list[1].key.as_deref()),
+        ("order", Some("order-1"))
+    );
+
+    // Messages and signals are routed from other threads
+    let worker = {
+        let engine = Arc::clone(&engine);
+        std::thread::spawn(move || -> Result<()> {
+            engine.correlate_message("order-1", "Payment received", |_| {})?;
+            engine.correlate_message("order-1", "Shipped", |_| {})?;
+            engine.signal(signal, "Go")?;
+            Ok(())
+        })
+    };
+    worker.join().unwrap()?;
+
+    assert!(engine.wait(signal).unwrap().is_ok());
+    let result = engine.wait(order).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 4);
+    assert_eq!(engine.status(order), None);
+    assert!(engine.list().is_empty());
+    Ok(())
+}
+
+#[test]
+fn engine_failed_and_broadcast() -> Result<()> {
+    let engine = Engine::new();
+    engine.deploy("failing", order_process(|_| Task::panic("out of stock"))?);
+    engine.deploy(
+        "signal",
+        ProcessBuilder::new("tests/files/signal_stalled.bpmn")?.build()?,
+    );
+
+    let failing = engine.start("failing", Default::default())?;
+    engine.correlate_message(failing, "Payment received", |_| {})?;
+    assert!(matches!(
+        engine.wait(failing),
+        Some(Err(RuntimeError::Panic(_)))
+    ));
+    assert!(matches!(
+        engine.correlate_message(failing, "Shipped", |_| {}),
+        Err(RuntimeError::UnknownInstance(_))
+    ));
+
+    let first = engine.start("signal", Default::default())?;
+    let second = engine.start("signal", Default::default())?;
+    wait_at(&engine, first, "Go");
+    wait_at(&engine, second, "Go");
+    assert_eq!(engine.broadcast_signal("Go"), 2);
+    assert!(engine.wait(first).unwrap().is_ok());
+    assert!(engine.wait(second).unwrap().is_ok());
+    Ok(())
+}
+
+#[test]
+fn external_task_complete() -> Result<()> {
+    let engine = Engine::new();
+    engine.deploy("invoice", invoice_process()?);
+    let id = engine.start("invoice", Mutex::new(Counter { count: 10 }))?;
+
+    let job = fetch_job(&engine, id, "worker-1");
+    assert_eq!((job.instance, job.data::<u32>()), (id, Some(&10)));
+    assert!(
+        engine
+            .fetch_and_lock("Send invoice", "worker-2", 1)
+            .is_empty()
+    );
+    assert!(matches!(
+        engine.complete_job(job.id, "worker-2", |_| {}),
+        Err(RuntimeError::UnknownJob(_))
+    ));
+    engine.complete_job(job.id, "worker-1", |data| data.lock().unwrap().count += 100)?;
+
+    let result = engine.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 111);
+    Ok(())
+}
+
+#[test]
+fn external_task_complete_twice() -> Result<()> {
+    let engine = Engine::new();
+    engine.deploy("invoice", invoice_process()?);
+    let id = engine.start("invoice", Default::default())?;
+
+    // Only one of the completions racing on the job is applied
+    let job = fetch_job(&engine, id, "worker-1");
+    let engine = &engine;
+    let results = std::thread::scope(|scope| {
+        [10, 100]
+            .map(|value| {
+                scope.spawn(move || {
+                    engine.complete_job(job.id, "worker-1", move |data| {
+                        data.lock().unwrap().count += value
+                    })
+                })
+            })
+            .map(|handle| handle.join().unwrap())
+    });
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    assert!(
+        results
+            .iter()
+            .any(|result| matches!(result, Err(RuntimeError::UnknownJob(_))))
+    );
+    assert!(matches!(
+        engine.fail_job(job.id, "worker-1", "REJECTED"),
+        Err(RuntimeError::UnknownJob(_))
+    ));
+
+    let count = engine.wait(id).unwrap()?.lock().unwrap().count;
+    assert!(count == 11 || count == 101);
+    Ok(())
+}
+
+#[test]
+fn external_task_fail() -> Result<()> {
+    let engine = Engine::new();
+    engine.deploy("invoice", invoice_process()?);
+    let id = engine.start("invoice", Default::default())?;
+
+    let job = fetch_job(&engine, id, "worker-1");
+    engine.fail_job(job.id, "worker-1", "REJECTED")?;
+    let result = engine.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 2);
+    Ok(())
+}
+
+#[test]
+fn external_task_outside_engine() -> Result<()> {
+    let result = invoice_process()?.run(Default::default());
+    assert!(matches!(
+        result,
+        Err(RuntimeError::Diagram(DiagramError::NotSupported(_)))
+    ));
+    Ok(())
+}
+
+#[test]
+fn user_task_inbox() -> Result<()> {
+    let engine = approval_engine()?;
+    let id = engine.start("approval", Default::default())?;
+
+    let approve = inbox_item(&engine, id, "Approve order");
+    assert_eq!(approve.instance, id);
+    assert_eq!(
+        approve.documentation.as_deref(),
+        Some("Check the order total")
+    );
+    assert_eq!(approve.lane.as_deref(), Some("Sales"));
+    assert_eq!(approve.assignee.as_deref(), Some("alice"));
+    assert!(matches!(
+        engine.claim(approve.id, "bob"),
+        Err(RuntimeError::NotAssigned(_, user)) if user == "bob"
+    ));
+    engine.reassign(approve.id, Some("bob"))?;
+    assert!(
+        engine
+            .complete_user_task(approve.id, "alice", |_| Task::Default)
+            .is_err()
+    );
+    engine.complete_user_task(approve.id, "bob", |data| {
+        data.lock().unwrap().count += 10;
+        Task::Default
+    })?;
+
+    let sign = inbox_item(&engine, id, "Sign contract");
+    assert_eq!(
+        (sign.lane.as_deref(), sign.assignee),
+        (Some("Finance"), None)
+    );
+    engine.claim(sign.id, "carol")?;
+    engine.complete_user_task(sign.id, "carol", |_| Task::Default)?;
+
+    let result = engine.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 11);
+    assert!(engine.inbox().is_empty());
+    assert!(matches!(
+        engine.claim(sign.id, "carol"),
+        Err(RuntimeError::UnknownUserTask(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn user_task_outcome_boundary() -> Result<()> {
+    let engine = approval_engine()?;
+    let id = engine.start("approval", Default::default())?;
+    let approve = inbox_item(&engine, id, "Approve order");
+    engine.complete_user_task(approve.id, "alice", |_| Task::error_code("DECLINED"))?;
+    let result = engine.wait(id).unwrap()?;
+    assert_eq!(result.lock().unwrap().count, 2);
+    Ok(())
+}
+
+#[test]
+fn user_task_complete_twice() -> Result<()> {
+    let engine = approval_engine()?;
+    let id = engine.start("approval", Default::default())?;
+
+    // Only one of the completions racing on the item is applied
+    let approve = inbox_item(&engine, id, "Approve order");
+    let engine = &engine;
+    let results = std::thread::scope(|scope| {
+        [Task::Default, Task::error_code("DECLINED")]
+            .map(|outcome| {
+                scope
+                    .spawn(move || engine.complete_user_task(approve.id, "alice", move |_| outcome))
+            })
+            .map(|handle| handle.join().unwrap())
+    });
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    assert!(
+        results
+            .iter()
+            .any(|result| matches!(result, Err(RuntimeError::UnknownUserTask(_))))
+    );
+
+    if results[0].is_ok() {
+        let sign = inbox_item(engine, id, "Sign contract");
+        engine.claim(sign.id, "carol")?;
+        engine.complete_user_task(sign.id, "carol", |_| Task::Default)?;
+    }
+    let result = engine.wait(id).unwrap()?;
+    assert_eq!(
+        result.lock().unwrap().count,
+        if results[0].is_ok() { 1 } else { 2 }
+    );
+    Ok(())
+}
+
+#[test]
+fn work_removed_when_instance_ends() -> Result<()> {
+    let engine = Engine::new();
+    engine.deploy(
+        "cancel",
+        ProcessBuilder::new("tests/files/work_withdrawn.bpmn")?
+            .human_task("Approve order")
+            .external_task("Send invoice", |_: &Mutex<Counter>| ())
+            .build()?,
+    );
+    let id = engine.start("cancel", Default::default())?;
+    let job = fetch_job(&engine, id, "worker-1");
+    let approve = inbox_item(&engine, id, "Approve order");
+
+    // The terminate end event withdraws the tokens waiting on the job and the user task
+    engine.correlate_message(id, "Cancel", |_| {})?;
+    engine.wait(id).unwrap()?;
+    assert!(engine.inbox().is_empty());
+    assert!(matches!(
+        engine.complete_job(job.id, "worker-1", |_| {}),
+        Err(RuntimeError::UnknownJob(_))
+    ));
+    assert!(matches!(
+        engine.complete_user_task(approve.id, "alice", |_| Task::Default),
+        Err(RuntimeError::UnknownUserTask(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn controller_pause_and_resume() -> Result<()> {
+    let controller = Controller::new();
+    let bpmn = {
+        let controller = controller.clone();
+        ProcessBuilder::new("tests/files/two_task.bpmn")?
+            .task(COUNT_1, move |input: &Mutex<Counter>| {
+                controller.pause();
+                func_cnt(1)(input)
+            })
+            .task(COUNT_2, func_cnt(2))
+            .build()?
+    };
+
+    std::thread::scope(|scope| {
+        let handle = scope.spawn(|| bpmn.run_with_controller(Default::default(), &controller));
+
+        // Paused by the first task, the token stops before the second
+        let token = wait_for_token(&controller, COUNT_2);
+        assert!(controller.status().paused);
+        assert_eq!(token.last_executed.as_deref(), Some(COUNT_1));
+        assert!(!token.waiting);
+
+        controller.resume();
+        let result = handle.join().unwrap()?;
+        assert_eq!(result.lock().unwrap().count, 3);
+        assert!(controller.status().tokens.is_empty());
+        Ok(())
+    })
+}
+
+#[test]
+fn controller_engine_instance() -> Result<()> {
+    let engine = Engine::new();
+    engine.deploy("order", order_process(func_cnt(1))?);
+    let id = engine.start_with_key("order", "order-1", Default::default())?;
+    wait_at(&engine, id, "Payment received");
+    let controller = engine.controller(id).expect("instance is running");
+    let token = wait_for_token(&controller, "Payment received");
+    assert!(token.waiting);
+    assert_eq!(token.last_executed.as_deref(), Some("StartEvent_1d8p3wz"));
+
+    // The message is caught while paused, but the token stops before the receive task runs
+    controller.pause();
+    assert_eq!(engine.status(id), Some(InstanceStatus::Paused));
+    engine.correlate_message("order-1", "Payment received", |_| {})?;
+    let status = controller.wait_until(|status| status.tokens.iter().any(|token| !token.waiting));
+    assert_eq!(status.tokens.len(), 1);
+    assert_eq!(status.tokens[0].element, "Payment received");
+    assert_eq!(
+        status.tokens[0].last_executed.as_deref(),
+        Some("StartEvent_1d8p3wz")
+    );
+
+    controller.resume();
+    engine.correlate_message("order-1", "Shipped", |_| {})?;
+    let result = engine.wait(id).expect("instance exists")?;
+    assert_eq!(result.lock().unwrap().count, 4);
+    assert!(engine.controller(id).is_none());
+    Ok(())
+}
+
+#[test]
+fn controller_paused_receive_task() -> Result<()> {
+    let received = Arc::new(AtomicU32::new(0));
+    let engine = Engine::new();
+    engine.deploy("order", {
+        let received = Arc::clone(&received);
+        ProcessBuilder::new("tests/files/message_correlation.bpmn")?
+            .task("Payment received", move |_: &Mutex<Counter>| {
+                received.fetch_add(1, Ordering::Relaxed);
+                Default::default()
+            })
+            .task(COUNT_1, func_cnt(1))
+            .task(COUNT_2, func_cnt(2))
+            .build()?
+    });
+    let id = engine.start("order", Default::default())?;
+    wait_at(&engine, id, "Payment received");
+    let controller = engine.controller(id).expect("instance is running");
+
+    // The receive task runs once the instance is resumed
+    controller.pause();
+    engine.correlate_message(id, "Payment received", |_| {})?;
+    controller.wait_until(|status| status.tokens.iter().any(|token| !token.waiting));
+    assert_eq!(received.load(Ordering::Relaxed), 0);
+
+    controller.resume();
+    engine.correlate_message(id, "Shipped", |_| {})?;
+    let result = engine.wait(id).expect("instance exists")?;
+    assert_eq!(result.lock().unwrap().count, 3);
+    assert_eq!(received.load(Ordering::Relaxed), 1);
+    Ok(())
+}